@@ -1,9 +1,17 @@
-extern crate bindgen;
+#[cfg(feature = "gpc")]
 use std::env;
+#[cfg(feature = "gpc")]
 use std::path::PathBuf;
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "gpc")]
+    build_gpc();
+}
+
+/// Compiles GPC and generates its bindings, only needed by the `gpc` feature.
+#[cfg(feature = "gpc")]
+fn build_gpc() {
     println!("cargo:rerun-if-changed=path/to/Cargo.lock");
     // Compile the C code
     cc::Build::new().file("src/gpc.c").compile("gpc");
@@ -11,6 +11,7 @@ fn main() {
         ],
         offset: Point2D { x: 0.0, y: 0.0 },
         rotation: 0.0,
+        mirrored: false,
     };
 
     let mut polygon2 = polygon1.clone();
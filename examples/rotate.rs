@@ -23,6 +23,7 @@ fn main() {
             y: HEIGHT as f64 / 2.0 / SCALE,
         },
         rotation: 0.0,
+        mirrored: false,
     };
 
     let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
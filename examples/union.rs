@@ -19,6 +19,7 @@ fn main() {
         ],
         offset: Point2D::from_xy(20.0, 20.0),
         rotation: 0.0,
+        mirrored: false,
     };
 
     let triangle = Polygon {
@@ -29,6 +30,7 @@ fn main() {
         ],
         offset: Point2D::from_xy(50.0, 15.0),
         rotation: PI / 2.0,
+        mirrored: false,
     };
 
     let mut union = square.clip_polygon(&triangle, ClipOp::Xor).unwrap();
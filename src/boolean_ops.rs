@@ -0,0 +1,525 @@
+//! Boolean operations on polygons in pure Rust.
+//!
+//! Every edge of both polygons is split at its intersections with all other edges,
+//! so that no two pieces cross. Each piece then either lies inside the other polygon,
+//! outside of it, or coincides with a piece of the other polygon. The operation decides
+//! which pieces are kept and the kept pieces are chained into closed contours.
+//!
+//! Like GPC, the contours of each polygon are combined with the even-odd rule
+//! and hole flags of the inputs are ignored. Outer contours of the result are
+//! clockwise and holes are counter-clockwise.
+use crate::clip::ClipOp;
+use crate::point::Point2D;
+use num_traits::{NumCast, ToPrimitive};
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+type Point = [f64; 2];
+
+/// Applies a boolean operation to two sets of contours flagged as holes or not.
+/// Every contour of the result is returned together with its hole flag.
+pub fn clip_contours<T: Point2D>(
+    subject: &[(Vec<T>, bool)],
+    clip: &[(Vec<T>, bool)],
+    op: ClipOp,
+) -> Vec<(Vec<T>, bool)> {
+    let subject = normalize(subject);
+    let clip = normalize(clip);
+    let eps = tolerance(subject.iter().chain(clip.iter()).flatten());
+
+    // split every edge at its intersections with every other edge
+    let edges = subject
+        .iter()
+        .map(|contour| (0, contour))
+        .chain(clip.iter().map(|contour| (1, contour)))
+        .flat_map(|(source, contour)| {
+            (0..contour.len()).map(move |i| (source, contour[i], contour[(i + 1) % contour.len()]))
+        })
+        .collect::<Vec<_>>();
+    let mut splits = vec![vec![0.0, 1.0]; edges.len()];
+    for i in 0..edges.len() {
+        for j in (i + 1)..edges.len() {
+            let (_, a_start, a_end) = edges[i];
+            let (_, b_start, b_end) = edges[j];
+            for (t, u) in intersections(a_start, a_end, b_start, b_end, eps) {
+                splits[i].extend(t);
+                splits[j].extend(u);
+            }
+        }
+    }
+
+    let mut points = PointPool::new(eps);
+    let mut pieces: [Vec<(usize, usize)>; 2] = [vec![], vec![]];
+    for ((source, start, end), mut params) in edges.into_iter().zip(splits) {
+        params.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let ids = params
+            .into_iter()
+            .map(|t| points.id(lerp(start, end, t)))
+            .collect::<Vec<_>>();
+        for (u, v) in ids.iter().zip(ids.iter().skip(1)) {
+            if u != v {
+                pieces[source].push((*u, *v));
+            }
+        }
+    }
+
+    let edge_sets = [
+        pieces[0].iter().copied().collect::<HashSet<_>>(),
+        pieces[1].iter().copied().collect::<HashSet<_>>(),
+    ];
+    let polygons = [&subject, &clip];
+    let mut kept = vec![];
+    for (source, source_pieces) in pieces.iter().enumerate() {
+        let other = 1 - source;
+        for &(u, v) in source_pieces.iter() {
+            let location = if edge_sets[other].contains(&(u, v)) {
+                Location::Shared
+            } else if edge_sets[other].contains(&(v, u)) {
+                Location::Opposite
+            } else if inside(&midpoint(points.get(u), points.get(v)), polygons[other]) {
+                Location::Inside
+            } else {
+                Location::Outside
+            };
+            if let Some(reversed) = keep(op, source == 0, location) {
+                kept.push(if reversed { (v, u) } else { (u, v) });
+            }
+        }
+    }
+
+    chain(&kept, &points, eps)
+        .into_iter()
+        .map(|contour| {
+            // contours are counter-clockwise around the region, so holes are clockwise
+            let hole = signed_area(&contour) < 0.0;
+            let points = contour
+                .iter()
+                .rev()
+                .map(|point| {
+                    T::from_xy(
+                        <T::Value as NumCast>::from(point[0]).unwrap(),
+                        <T::Value as NumCast>::from(point[1]).unwrap(),
+                    )
+                })
+                .collect();
+            (points, hole)
+        })
+        .collect()
+}
+
+/// Where a piece of an edge lies relative to the other polygon.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Inside,
+    Outside,
+    /// Coincides with an edge of the other polygon going the same way.
+    Shared,
+    /// Coincides with an edge of the other polygon going the opposite way.
+    Opposite,
+}
+
+/// Returns whether a piece of the subject (or clip) polygon belongs to the result,
+/// and if so whether it has to be reversed to keep the result on its left.
+/// Shared pieces are only taken from the subject so they are not kept twice.
+fn keep(op: ClipOp, subject: bool, location: Location) -> Option<bool> {
+    match (op, subject, location) {
+        (ClipOp::Union, _, Location::Outside) => Some(false),
+        (ClipOp::Union, true, Location::Shared) => Some(false),
+        (ClipOp::Intersection, _, Location::Inside) => Some(false),
+        (ClipOp::Intersection, true, Location::Shared) => Some(false),
+        (ClipOp::Difference, true, Location::Outside) => Some(false),
+        (ClipOp::Difference, true, Location::Opposite) => Some(false),
+        (ClipOp::Difference, false, Location::Inside) => Some(true),
+        (ClipOp::Xor, _, Location::Outside) => Some(false),
+        (ClipOp::Xor, _, Location::Inside) => Some(true),
+        _ => None,
+    }
+}
+
+/// Converts the contours to f64, drops repeated and degenerate contours and orients them
+/// so that the region enclosed with the even-odd rule is on the left of every edge.
+fn normalize<T: Point2D>(contours: &[(Vec<T>, bool)]) -> Vec<Vec<Point>> {
+    let contours = contours
+        .iter()
+        .map(|(contour, _)| {
+            let mut points = contour
+                .iter()
+                .map(|point| [point.x().to_f64().unwrap(), point.y().to_f64().unwrap()])
+                .collect::<Vec<_>>();
+            points.dedup();
+            while points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            points
+        })
+        .filter(|points| points.len() >= 3 && signed_area(points) != 0.0)
+        .collect::<Vec<_>>();
+
+    contours
+        .iter()
+        .enumerate()
+        .map(|(i, contour)| {
+            let depth = contours
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| *j != i && inside(&contour[0], std::slice::from_ref(other)))
+                .count();
+            let counter_clockwise = signed_area(contour) > 0.0;
+            if counter_clockwise == (depth % 2 == 0) {
+                contour.clone()
+            } else {
+                contour.iter().rev().copied().collect()
+            }
+        })
+        .collect()
+}
+
+/// A distance below which points are considered equal, relative to the size of the input.
+fn tolerance<'a>(points: impl Iterator<Item = &'a Point>) -> f64 {
+    let extent = points.fold(1.0f64, |extent, point| {
+        extent.max(point[0].abs()).max(point[1].abs())
+    });
+    extent * 1e-10
+}
+
+/// Twice the signed area, positive for counter-clockwise contours.
+fn signed_area(contour: &[Point]) -> f64 {
+    (0..contour.len())
+        .map(|i| {
+            let a = contour[i];
+            let b = contour[(i + 1) % contour.len()];
+            a[0] * b[1] - a[1] * b[0]
+        })
+        .sum()
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    if t <= 0.0 {
+        a
+    } else if t >= 1.0 {
+        b
+    } else {
+        [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+    }
+}
+
+fn midpoint(a: Point, b: Point) -> Point {
+    lerp(a, b, 0.5)
+}
+
+fn length(a: Point) -> f64 {
+    a[0].hypot(a[1])
+}
+
+/// Returns the parameters along both segments where they intersect or touch.
+/// Collinear overlapping segments return the endpoints of the overlap.
+fn intersections(
+    a_start: Point,
+    a_end: Point,
+    b_start: Point,
+    b_end: Point,
+    eps: f64,
+) -> Vec<(Option<f64>, Option<f64>)> {
+    let da = sub(a_end, a_start);
+    let db = sub(b_end, b_start);
+    let (la, lb) = (length(da), length(db));
+    if la <= eps || lb <= eps {
+        return vec![];
+    }
+    // bounding box rejection
+    if a_start[0].max(a_end[0]) + eps < b_start[0].min(b_end[0])
+        || b_start[0].max(b_end[0]) + eps < a_start[0].min(a_end[0])
+        || a_start[1].max(a_end[1]) + eps < b_start[1].min(b_end[1])
+        || b_start[1].max(b_end[1]) + eps < a_start[1].min(a_end[1])
+    {
+        return vec![];
+    }
+
+    // signed distances of the endpoints of one segment to the line through the other
+    let distance_to_a = |point: Point| cross(da, sub(point, a_start)) / la;
+    let distance_to_b = |point: Point| cross(db, sub(point, b_start)) / lb;
+    let project_on_a = |point: Point| {
+        (sub(point, a_start)[0] * da[0] + sub(point, a_start)[1] * da[1]) / (la * la)
+    };
+    let project_on_b = |point: Point| {
+        (sub(point, b_start)[0] * db[0] + sub(point, b_start)[1] * db[1]) / (lb * lb)
+    };
+    let on_a = |t: f64| t > -eps / la && t < 1.0 + eps / la;
+    let on_b = |u: f64| u > -eps / lb && u < 1.0 + eps / lb;
+
+    let mut result = vec![];
+    // endpoints touching the other segment, this also covers collinear overlaps
+    for point in [b_start, b_end] {
+        let t = project_on_a(point);
+        if distance_to_a(point).abs() <= eps && on_a(t) {
+            result.push((Some(t.clamp(0.0, 1.0)), None));
+        }
+    }
+    for point in [a_start, a_end] {
+        let u = project_on_b(point);
+        if distance_to_b(point).abs() <= eps && on_b(u) {
+            result.push((None, Some(u.clamp(0.0, 1.0))));
+        }
+    }
+
+    // proper crossing
+    let (d0, d1) = (distance_to_a(b_start), distance_to_a(b_end));
+    let (d2, d3) = (distance_to_b(a_start), distance_to_b(a_end));
+    if ((d0 > eps && d1 < -eps) || (d0 < -eps && d1 > eps))
+        && ((d2 > eps && d3 < -eps) || (d2 < -eps && d3 > eps))
+    {
+        let u = d0 / (d0 - d1);
+        let t = d2 / (d2 - d3);
+        result.push((Some(t), Some(u)));
+    }
+    result
+}
+
+/// Even-odd point in polygon test.
+fn inside(point: &Point, contours: &[Vec<Point>]) -> bool {
+    let mut inside = false;
+    for contour in contours {
+        for i in 0..contour.len() {
+            let a = contour[i];
+            let b = contour[(i + 1) % contour.len()];
+            if (a[1] > point[1]) != (b[1] > point[1])
+                && point[0] < a[0] + (point[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+            {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Merges points closer than a tolerance so split edges share their endpoints.
+struct PointPool {
+    eps: f64,
+    points: Vec<Point>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl PointPool {
+    fn new(eps: f64) -> Self {
+        Self {
+            eps,
+            points: vec![],
+            grid: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: Point) -> (i64, i64) {
+        let size = self.eps * 4.0;
+        (
+            (point[0] / size).floor() as i64,
+            (point[1] / size).floor() as i64,
+        )
+    }
+
+    fn id(&mut self, point: Point) -> usize {
+        let (x, y) = self.cell(point);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(ids) = self.grid.get(&(x + dx, y + dy)) {
+                    for &id in ids {
+                        if length(sub(self.points[id], point)) <= self.eps {
+                            return id;
+                        }
+                    }
+                }
+            }
+        }
+        self.points.push(point);
+        self.grid
+            .entry((x, y))
+            .or_default()
+            .push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+
+    fn get(&self, id: usize) -> Point {
+        self.points[id]
+    }
+}
+
+/// Chains directed edges into closed contours. Where several edges leave a vertex
+/// the sharpest left turn is taken, so regions touching at a vertex stay separate.
+fn chain(edges: &[(usize, usize)], points: &PointPool, eps: f64) -> Vec<Vec<Point>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, (u, _)) in edges.iter().enumerate() {
+        outgoing.entry(*u).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut contours = vec![];
+
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = edges[first].0;
+        let mut ids = vec![start];
+        let mut current = first;
+        let closed = loop {
+            let (u, v) = edges[current];
+            if v == start {
+                break true;
+            }
+            ids.push(v);
+            let back = sub(points.get(u), points.get(v));
+            let back_angle = back[1].atan2(back[0]);
+            let next = outgoing.get(&v).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .filter(|i| !used[**i])
+                    .min_by(|a, b| {
+                        let angle = |i: usize| {
+                            let direction = sub(points.get(edges[i].1), points.get(v));
+                            let clockwise = (back_angle - direction[1].atan2(direction[0]))
+                                .rem_euclid(2.0 * PI);
+                            if clockwise == 0.0 {
+                                2.0 * PI
+                            } else {
+                                clockwise
+                            }
+                        };
+                        angle(**a).partial_cmp(&angle(**b)).unwrap()
+                    })
+                    .copied()
+            });
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    current = next;
+                }
+                None => break false,
+            }
+        };
+        if closed {
+            let contour = simplify(ids.iter().map(|id| points.get(*id)).collect(), eps);
+            if contour.len() >= 3 {
+                contours.push(contour);
+            }
+        }
+    }
+    contours
+}
+
+/// Removes vertices in the middle of straight runs.
+fn simplify(mut contour: Vec<Point>, eps: f64) -> Vec<Point> {
+    let mut i = 0;
+    while i < contour.len() && contour.len() >= 3 {
+        let n = contour.len();
+        let previous = contour[(i + n - 1) % n];
+        let next = contour[(i + 1) % n];
+        let direction = sub(next, previous);
+        let straight = cross(direction, sub(contour[i], previous)).abs() <= eps * length(direction)
+            && (sub(contour[i], previous)[0] * direction[0]
+                + sub(contour[i], previous)[1] * direction[1])
+                > 0.0
+            && (sub(next, contour[i])[0] * direction[0] + sub(next, contour[i])[1] * direction[1])
+                > 0.0;
+        if straight {
+            contour.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    contour
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::Point2D;
+
+    fn square(x: f64, y: f64, size: f64) -> (Vec<Point2D>, bool) {
+        (
+            vec![
+                Point2D { x, y },
+                Point2D { x: x + size, y },
+                Point2D {
+                    x: x + size,
+                    y: y + size,
+                },
+                Point2D { x, y: y + size },
+            ],
+            false,
+        )
+    }
+
+    fn area(contours: &[(Vec<Point2D>, bool)]) -> f64 {
+        contours
+            .iter()
+            .map(|(contour, _)| {
+                signed_area(&contour.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>()) / 2.0
+            })
+            .sum::<f64>()
+            .abs()
+    }
+
+    #[test]
+    fn test_overlapping_squares() {
+        let a = [square(0.0, 0.0, 10.0)];
+        let b = [square(5.0, 5.0, 10.0)];
+        let union = clip_contours(&a, &b, ClipOp::Union);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].0.len(), 8);
+        assert_eq!(area(&union), 175.0);
+        assert_eq!(area(&clip_contours(&a, &b, ClipOp::Intersection)), 25.0);
+        assert_eq!(area(&clip_contours(&a, &b, ClipOp::Difference)), 75.0);
+        let xor = clip_contours(&a, &b, ClipOp::Xor);
+        assert_eq!(xor.len(), 2);
+        assert_eq!(area(&xor), 150.0);
+    }
+
+    #[test]
+    fn test_shared_edges() {
+        let a = [square(0.0, 0.0, 10.0)];
+        let b = [square(10.0, 0.0, 10.0)];
+        let union = clip_contours(&a, &b, ClipOp::Union);
+        assert_eq!(union.len(), 1);
+        // the shared edge disappears along with the vertices on straight runs
+        assert_eq!(union[0].0.len(), 4);
+        assert!(clip_contours(&a, &b, ClipOp::Intersection).is_empty());
+        assert_eq!(area(&clip_contours(&a, &b, ClipOp::Difference)), 100.0);
+
+        let same = clip_contours(&a, &a, ClipOp::Union);
+        assert_eq!(same.len(), 1);
+        assert_eq!(area(&same), 100.0);
+        assert!(clip_contours(&a, &a, ClipOp::Difference).is_empty());
+    }
+
+    #[test]
+    fn test_holes() {
+        let a = [square(0.0, 0.0, 10.0)];
+        let b = [square(3.0, 3.0, 4.0)];
+        let difference = clip_contours(&a, &b, ClipOp::Difference);
+        assert_eq!(difference.len(), 2);
+        assert_eq!(difference.iter().filter(|(_, hole)| *hole).count(), 1);
+        assert_eq!(area(&difference[..1]) - area(&difference[1..]), 84.0);
+
+        // even-odd: the inner square of the subject is a hole regardless of its flag
+        let ring = [square(0.0, 0.0, 10.0), square(3.0, 3.0, 4.0)];
+        let union = clip_contours(&ring, &[square(4.0, 4.0, 2.0)], ClipOp::Union);
+        assert_eq!(union.len(), 3);
+        assert_eq!(union.iter().filter(|(_, hole)| *hole).count(), 1);
+    }
+
+    #[test]
+    fn test_orientation() {
+        let a = [square(0.0, 0.0, 10.0)];
+        let union = clip_contours(&a, &[], ClipOp::Union);
+        let contour = union[0].0.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>();
+        assert!(signed_area(&contour) < 0.0);
+    }
+}
@@ -0,0 +1,322 @@
+//! Inflating and deflating polygons, eg. to keep a gap for the kerf of a cutting tool.
+//!
+//! Every boundary edge sweeps a quad of the given width to the side that grows,
+//! and the gaps the quads leave at corners are filled with joins. Inflating unions
+//! the quads and joins with the polygon, deflating subtracts them from it.
+//! Since holes are boundaries too, inflating a polygon shrinks its holes and deflating grows them.
+use crate::clip::{clip_contours, ClipOp, Clippable};
+use crate::multi_polygon::{nest_contours, MultiPolygon};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use num_traits::{Float, NumCast, One, ToPrimitive, Zero};
+
+/// How the corners of an inflated (or deflated) polygon are shaped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinType<T> {
+    /// Sharp corners, squared off where the tip would be further than
+    /// the given multiple of the offset from the original corner.
+    Miter(T),
+    /// Circular arcs, approximated so they deviate at most the given distance from a true arc.
+    Round(T),
+    /// Corners cut off at the offset distance from the original corner.
+    Square,
+}
+
+pub trait Buffer: Clippable {
+    /// Grows the polygon by `delta`, or shrinks it if `delta` is negative.
+    /// The result has no offset or rotation, its vertices are the transformed vertices.
+    fn buffer(
+        &self,
+        delta: <Self::Point as Point2D>::Value,
+        join: JoinType<<Self::Point as Point2D>::Value>,
+    ) -> Vec<MultiPolygon<Self>> {
+        MultiPolygon::new(self.clone(), vec![]).buffer(delta, join)
+    }
+}
+
+impl<P: Clippable> MultiPolygon<P> {
+    /// Grows the outer contour and shrinks the holes by `delta`, or the opposite if `delta` is negative.
+    /// Deflating can split the polygon into several pieces or make it disappear entirely.
+    /// The result has no offset or rotation, its vertices are the transformed vertices.
+    pub fn buffer(
+        &self,
+        delta: <P::Point as Point2D>::Value,
+        join: JoinType<<P::Point as Point2D>::Value>,
+    ) -> Vec<MultiPolygon<P>> {
+        let region = oriented_contours(self);
+        let region_contours = region
+            .iter()
+            .map(|contour| (contour.clone(), false))
+            .collect::<Vec<_>>();
+        if delta == Zero::zero() {
+            return nest_contours(clip_contours(&region_contours, &[], ClipOp::Union));
+        }
+
+        let inflate = delta > Zero::zero();
+        let distance = delta.abs();
+        let mut pieces = vec![];
+        for contour in region.iter() {
+            let n = contour.len();
+            for i in 0..n {
+                let previous = contour[(i + n - 1) % n];
+                let vertex = contour[i];
+                let next = contour[(i + 1) % n];
+                let Some(normal) = side_normal(vertex, next, inflate) else {
+                    continue;
+                };
+                pieces.push(vec![
+                    vertex,
+                    next,
+                    next + normal * distance,
+                    vertex + normal * distance,
+                ]);
+                if let Some(join) = corner(previous, vertex, next, distance, inflate, join) {
+                    pieces.push(join);
+                }
+            }
+        }
+
+        let swept = union_all(pieces);
+        let op = if inflate {
+            ClipOp::Union
+        } else {
+            ClipOp::Difference
+        };
+        nest_contours(clip_contours(&region_contours, &swept, op))
+    }
+}
+
+/// Returns the transformed contours of the polygon, the outer contour counter-clockwise
+/// and holes clockwise, so the polygon is on the left of every edge.
+fn oriented_contours<P: Polygon>(multi_polygon: &MultiPolygon<P>) -> Vec<Vec<P::Point>> {
+    multi_polygon
+        .contours()
+        .into_iter()
+        .filter(|(contour, _)| contour.len() >= 3)
+        .map(|(mut contour, hole)| {
+            // a negative area is counter-clockwise
+            let counter_clockwise = signed_area(&contour) < Zero::zero();
+            if counter_clockwise == hole {
+                contour.reverse();
+            }
+            contour
+        })
+        .collect()
+}
+
+fn signed_area<T: Point2D>(contour: &[T]) -> T::Value {
+    (0..contour.len())
+        .map(|i| {
+            let start = contour[i];
+            let end = contour[(i + 1) % contour.len()];
+            (start.x() + end.x()) * (start.y() - end.y())
+        })
+        .fold(Zero::zero(), |sum: T::Value, x| sum + x)
+}
+
+fn cross<T: Point2D>(a: T, b: T) -> T::Value {
+    a.x() * b.y() - a.y() * b.x()
+}
+
+/// Unit normal of the edge pointing to the right (outside) when inflating
+/// and to the left (inside) when deflating. None for degenerate edges.
+fn side_normal<T: Point2D>(start: T, end: T, inflate: bool) -> Option<T> {
+    let direction = (end - start).normalized()?;
+    Some(if inflate {
+        T::from_xy(direction.y(), -direction.x())
+    } else {
+        T::from_xy(-direction.y(), direction.x())
+    })
+}
+
+/// Returns the join filling the gap between the quads of two edges meeting at `vertex`,
+/// or None if the quads overlap there.
+fn corner<T: Point2D>(
+    previous: T,
+    vertex: T,
+    next: T,
+    distance: T::Value,
+    inflate: bool,
+    join: JoinType<T::Value>,
+) -> Option<Vec<T>> {
+    // the polygon is on the left, so the quads leave a gap where it turns away from the growing side
+    let turn = cross(vertex - previous, next - vertex);
+    let gap = if inflate {
+        turn > Zero::zero()
+    } else {
+        turn < Zero::zero()
+    };
+    if !gap {
+        return None;
+    }
+
+    let previous_normal = side_normal(previous, vertex, inflate)?;
+    let normal = side_normal(vertex, next, inflate)?;
+    let one = T::Value::one();
+    let two = one + one;
+    let start = vertex + previous_normal * distance;
+    let end = vertex + normal * distance;
+    // half of the angle between the normals
+    let half_angle = previous_normal.dot(&normal).max(-one).min(one).acos() / two;
+
+    let square = || {
+        // cut the corner at the offset distance along the bisector
+        let along = distance * (half_angle / two).tan();
+        let previous_direction = (vertex - previous).normalized()?;
+        let direction = (next - vertex).normalized()?;
+        Some(vec![
+            vertex,
+            start,
+            start + previous_direction * along,
+            end - direction * along,
+            end,
+        ])
+    };
+
+    match join {
+        JoinType::Miter(limit) => {
+            let tip = one / half_angle.cos();
+            if tip > limit {
+                return square();
+            }
+            let bisector = (previous_normal + normal).normalized()?;
+            Some(vec![
+                vertex,
+                start,
+                vertex + bisector * (distance * tip),
+                end,
+            ])
+        }
+        JoinType::Square => square(),
+        JoinType::Round(tolerance) => {
+            let tolerance = tolerance.max(distance * <T::Value as NumCast>::from(1e-6)?);
+            let step = if tolerance >= distance {
+                half_angle * two
+            } else {
+                two * (one - tolerance / distance).acos()
+            };
+            let steps = ((half_angle * two) / step).ceil().to_usize()?.max(1);
+            let sign = if cross(previous_normal, normal) > Zero::zero() {
+                one
+            } else {
+                -one
+            };
+            let mut points = vec![vertex, start];
+            for i in 1..steps {
+                let angle = sign * half_angle * two * <T::Value as NumCast>::from(i)?
+                    / <T::Value as NumCast>::from(steps)?;
+                points.push(vertex + previous_normal.rotate(angle) * distance);
+            }
+            points.push(end);
+            Some(points)
+        }
+    }
+}
+
+/// Unions possibly overlapping contours, merging pairs so intermediate results stay small.
+fn union_all<T: Point2D>(contours: Vec<Vec<T>>) -> Vec<(Vec<T>, bool)> {
+    let mut layers = contours
+        .into_iter()
+        .map(|contour| vec![(contour, false)])
+        .collect::<Vec<_>>();
+    while layers.len() > 1 {
+        let mut merged = vec![];
+        let mut iter = layers.into_iter();
+        while let Some(a) = iter.next() {
+            merged.push(match iter.next() {
+                Some(b) => clip_contours(&a, &b, ClipOp::Union),
+                None => a,
+            });
+        }
+        layers = merged;
+    }
+    layers.pop().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::Polygon;
+
+    fn square(size: f64) -> Polygon {
+        Polygon::from_tuples([(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)])
+    }
+
+    #[test]
+    fn test_inflate_square() {
+        let miter = square(10.0).buffer(1.0, JoinType::Miter(2.0));
+        assert_eq!(miter.len(), 1);
+        approx::assert_abs_diff_eq!(miter[0].area(), 144.0, epsilon = 1e-9);
+        let bbox = miter[0].bounding_box();
+        approx::assert_abs_diff_eq!(bbox.min_x, -1.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(bbox.max_y, 11.0, epsilon = 1e-9);
+
+        // the square join cuts every corner at distance 1 from the original corner
+        let squared = square(10.0).buffer(1.0, JoinType::Square);
+        let cut = (1.0 - (std::f64::consts::PI / 8.0).tan()).powi(2) / 2.0;
+        approx::assert_abs_diff_eq!(squared[0].area(), 144.0 - 4.0 * cut, epsilon = 1e-9);
+
+        let round = square(10.0).buffer(1.0, JoinType::Round(0.01));
+        let area = round[0].area();
+        assert!(area < 140.0 + std::f64::consts::PI + 1e-9);
+        assert!(area > 140.0 + std::f64::consts::PI - 0.1);
+    }
+
+    #[test]
+    fn test_deflate_square() {
+        let deflated = square(10.0).buffer(-1.0, JoinType::Round(0.01));
+        assert_eq!(deflated.len(), 1);
+        approx::assert_abs_diff_eq!(deflated[0].area(), 64.0, epsilon = 1e-9);
+        assert!(square(10.0).buffer(-5.0, JoinType::Square).is_empty());
+    }
+
+    #[test]
+    fn test_buffer_holes() {
+        let frame = MultiPolygon::new(
+            square(10.0),
+            vec![Polygon::from_tuples([
+                (3.0, 3.0),
+                (7.0, 3.0),
+                (7.0, 7.0),
+                (3.0, 7.0),
+            ])],
+        );
+        let inflated = frame.buffer(1.0, JoinType::Miter(2.0));
+        assert_eq!(inflated.len(), 1);
+        assert_eq!(inflated[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(inflated[0].area(), 144.0 - 4.0, epsilon = 1e-9);
+
+        let deflated = frame.buffer(-1.0, JoinType::Miter(2.0));
+        assert_eq!(deflated[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(deflated[0].area(), 64.0 - 36.0, epsilon = 1e-9);
+
+        // the hole closes up entirely
+        let closed = frame.buffer(2.0, JoinType::Miter(2.0));
+        assert!(closed[0].holes().is_empty());
+        approx::assert_abs_diff_eq!(closed[0].area(), 196.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_deflate_splits_polygon() {
+        // two squares joined by a thin bridge
+        let dumbbell = Polygon::from_tuples([
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.5),
+            (6.0, 1.5),
+            (6.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 4.0),
+            (6.0, 4.0),
+            (6.0, 2.5),
+            (4.0, 2.5),
+            (4.0, 4.0),
+            (0.0, 4.0),
+        ]);
+        let deflated = dumbbell.buffer(-1.0, JoinType::Miter(2.0));
+        assert_eq!(deflated.len(), 2);
+        for piece in deflated.iter() {
+            approx::assert_abs_diff_eq!(piece.area(), 4.0, epsilon = 1e-9);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Boolean operations backed by the GPC C library, enabled with the `gpc` feature.
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use super::ClipOp;
+use crate::multi_polygon::{nest_contours, MultiPolygon};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use num_traits::NumCast;
+use num_traits::ToPrimitive;
+use std::os::raw::c_int;
+
+// Include the bindings generated by bindgen
+include!(concat!(env!("OUT_DIR"), "/gpc.rs"));
+
+/// A gpc polygon whose memory is owned by Rust.
+/// The raw polygon is private so its pointers always point into the buffers below.
+pub struct ManagedGpcPolygon {
+    polygon: gpc_polygon,
+    _contours: Vec<gpc_vertex_list>, // Contours to keep them alive
+    _vertices: Vec<gpc_vertex>,      // Vertices to keep them alive
+    _holes: Vec<c_int>,              // Hole flags to keep them alive
+}
+
+impl ManagedGpcPolygon {
+    /// Builds a polygon from the vertices of every contour, in order.
+    pub fn to_polygon<P>(self) -> P
+    where
+        P: Polygon + From<Vec<P::Point>>,
+    {
+        P::from(
+            self.contours::<P::Point>()
+                .into_iter()
+                .flat_map(|(contour, _)| contour)
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl<P> From<&P> for ManagedGpcPolygon
+where
+    P: Polygon,
+{
+    fn from(polygon: &P) -> Self {
+        ManagedGpcPolygon::from_contours(&[(polygon.iter_vertices().collect::<Vec<_>>(), false)])
+    }
+}
+
+impl<P> From<&MultiPolygon<P>> for ManagedGpcPolygon
+where
+    P: Polygon,
+{
+    /// The outer contour and every hole become contours of the gpc polygon,
+    /// holes are flagged as such.
+    fn from(multi_polygon: &MultiPolygon<P>) -> Self {
+        ManagedGpcPolygon::from_contours(&multi_polygon.contours())
+    }
+}
+
+impl ManagedGpcPolygon {
+    /// Builds a gpc polygon from several contours, each flagged as a hole or not.
+    pub fn from_contours<T: Point2D>(contours: &[(Vec<T>, bool)]) -> Self {
+        let mut vertices: Vec<gpc_vertex> = contours
+            .iter()
+            .flat_map(|(contour, _)| contour.iter())
+            .map(|vertex| gpc_vertex {
+                x: vertex.x().to_f64().unwrap(),
+                y: vertex.y().to_f64().unwrap(),
+            })
+            .collect();
+
+        let mut start = 0;
+        let mut vertex_lists = vec![];
+        for (contour, _) in contours.iter() {
+            vertex_lists.push(gpc_vertex_list {
+                num_vertices: contour.len() as c_int,
+                vertex: vertices[start..].as_mut_ptr(),
+            });
+            start += contour.len();
+        }
+        let mut contour_lists = vertex_lists;
+        let mut holes: Vec<c_int> = contours.iter().map(|(_, hole)| *hole as c_int).collect();
+
+        // gpc temporarily negates vertex counts of contours while clipping,
+        // so the pointers are derived from mutable borrows
+        let polygon = gpc_polygon {
+            num_contours: contours.len() as c_int,
+            hole: holes.as_mut_ptr(),
+            contour: contour_lists.as_mut_ptr(),
+        };
+
+        ManagedGpcPolygon {
+            polygon,
+            _contours: contour_lists,
+            _vertices: vertices,
+            _holes: holes,
+        }
+    }
+
+    pub fn num_contours(&self) -> usize {
+        self.polygon.num_contours as usize
+    }
+
+    /// Returns every contour together with its hole flag.
+    pub fn contours<T: Point2D>(&self) -> Vec<(Vec<T>, bool)> {
+        // the pointers of a managed polygon always point into its buffers
+        unsafe { read_contours(&self.polygon) }
+    }
+
+    /// Groups the contours into multi polygons, each hole nested in the outer contour containing it.
+    pub fn to_multi_polygons<P>(&self) -> Vec<MultiPolygon<P>>
+    where
+        P: Polygon + From<Vec<P::Point>>,
+    {
+        nest_contours(self.contours())
+    }
+
+    /// Applies a boolean operation with self as the subject polygon.
+    /// Both polygons are borrowed mutably because gpc modifies them while clipping.
+    pub fn clip(&mut self, other: &mut ManagedGpcPolygon, op: ClipOp) -> GpcPolygon {
+        let mut result = GpcPolygon::empty();
+        // gpc only allocates and writes the result, it has no global state,
+        // so clipping distinct polygons from several threads is fine
+        unsafe {
+            gpc_polygon_clip(
+                op.gpc_op(),
+                &mut self.polygon,
+                &mut other.polygon,
+                &mut result.polygon,
+            );
+        }
+        result
+    }
+}
+
+/// A gpc polygon allocated by gpc, such as the result of a clip.
+/// The memory is released with `gpc_free_polygon` when it is dropped.
+pub struct GpcPolygon {
+    polygon: gpc_polygon,
+}
+
+impl GpcPolygon {
+    fn empty() -> Self {
+        GpcPolygon {
+            polygon: gpc_polygon {
+                num_contours: 0,
+                contour: std::ptr::null_mut(),
+                hole: std::ptr::null_mut(),
+            },
+        }
+    }
+
+    pub fn num_contours(&self) -> usize {
+        self.polygon.num_contours.max(0) as usize
+    }
+
+    /// Returns every contour together with its hole flag.
+    pub fn contours<T: Point2D>(&self) -> Vec<(Vec<T>, bool)> {
+        // only gpc writes the polygon, and it always leaves it valid
+        unsafe { read_contours(&self.polygon) }
+    }
+
+    /// Groups the contours into multi polygons, each hole nested in the outer contour containing it.
+    pub fn to_multi_polygons<P>(&self) -> Vec<MultiPolygon<P>>
+    where
+        P: Polygon + From<Vec<P::Point>>,
+    {
+        nest_contours(self.contours())
+    }
+}
+
+impl Drop for GpcPolygon {
+    fn drop(&mut self) {
+        unsafe { gpc_free_polygon(&mut self.polygon) }
+    }
+}
+
+/// Reads the contours and hole flags of a gpc polygon.
+///
+/// # Safety
+/// `polygon` must point to `num_contours` valid contours and,
+/// unless it is null, `num_contours` hole flags.
+unsafe fn read_contours<T: Point2D>(polygon: &gpc_polygon) -> Vec<(Vec<T>, bool)> {
+    let mut contours = vec![];
+    for contour_index in 0..polygon.num_contours.max(0) as usize {
+        let vertex_list = *polygon.contour.add(contour_index);
+        let hole = !polygon.hole.is_null() && *polygon.hole.add(contour_index) != 0;
+        let points = (0..vertex_list.num_vertices.max(0) as usize)
+            .map(|vertex_index| {
+                let vertex = *vertex_list.vertex.add(vertex_index);
+                T::from_xy(
+                    <T::Value as NumCast>::from(vertex.x).unwrap(),
+                    <T::Value as NumCast>::from(vertex.y).unwrap(),
+                )
+            })
+            .collect();
+        contours.push((points, hole));
+    }
+    contours
+}
+
+/// Applies a boolean operation to two sets of contours flagged as holes or not.
+/// Every contour of the result is returned together with its hole flag.
+pub fn clip_contours<T: Point2D>(
+    subject: &[(Vec<T>, bool)],
+    clip: &[(Vec<T>, bool)],
+    op: ClipOp,
+) -> Vec<(Vec<T>, bool)> {
+    ManagedGpcPolygon::from_contours(subject)
+        .clip(&mut ManagedGpcPolygon::from_contours(clip), op)
+        .contours()
+}
+
+impl ClipOp {
+    fn gpc_op(&self) -> gpc_op {
+        match self {
+            ClipOp::Union => gpc_op_GPC_UNION,
+            ClipOp::Intersection => gpc_op_GPC_INT,
+            ClipOp::Difference => gpc_op_GPC_DIFF,
+            ClipOp::Xor => gpc_op_GPC_XOR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_round_trip_conversion() {
+        use super::ManagedGpcPolygon;
+        use crate::kernelf64::{Point2D, Polygon};
+        use crate::point::Point2D as _;
+        use crate::polygon::Polygon as _;
+        let polygon = Polygon::from(vec![
+            Point2D::from_xy(0.0, 0.0),
+            Point2D::from_xy(1.0, 0.0),
+            Point2D::from_xy(1.0, 1.0),
+            Point2D::from_xy(0.0, 1.0),
+        ]);
+        let gpc_polygon = ManagedGpcPolygon::from(&polygon);
+        let reconstructed_polygon: Polygon = gpc_polygon.to_polygon();
+        reconstructed_polygon
+            .iter_vertices()
+            .zip(polygon.iter_vertices())
+            .for_each(|(a, b)| {
+                assert_eq!(a, b);
+            });
+    }
+
+    #[test]
+    fn test_multi_polygon_round_trip_keeps_holes() {
+        use super::ManagedGpcPolygon;
+        use crate::kernelf64::Polygon;
+        use crate::multi_polygon::MultiPolygon;
+
+        let multi_polygon = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![
+                Polygon::from_tuples([(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]),
+                Polygon::from_tuples([(5.0, 5.0), (8.0, 5.0), (8.0, 8.0)]),
+            ],
+        );
+        let gpc_polygon = ManagedGpcPolygon::from(&multi_polygon);
+        assert_eq!(gpc_polygon.num_contours(), 3);
+        assert_eq!(gpc_polygon.contours(), multi_polygon.contours());
+
+        let reconstructed: Vec<MultiPolygon<Polygon>> = gpc_polygon.to_multi_polygons();
+        assert_eq!(reconstructed.len(), 1);
+        assert_eq!(reconstructed[0].contours(), multi_polygon.contours());
+    }
+}
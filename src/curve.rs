@@ -0,0 +1,836 @@
+//! Curved segments (lines, circular arcs and Bézier curves) and closed contours made of them.
+//!
+//! Nesting works on polygons, so curves are flattened into polylines whose chords
+//! stay within a tolerance of the true curve. The curves themselves are kept
+//! so the original geometry can be exported after placing the flattened pieces.
+use crate::multi_polygon::MultiPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use num_traits::{Float, FloatConst, NumCast, One, ToPrimitive, Zero};
+
+pub trait Curve: Clone + std::fmt::Debug {
+    type Point: Point2D;
+
+    fn start(&self) -> Self::Point;
+    fn end(&self) -> Self::Point;
+
+    /// Returns the point of the curve at parameter `t` in [0, 1].
+    fn point_at(&self, t: <Self::Point as Point2D>::Value) -> Self::Point;
+
+    /// Returns the number of chords needed to stay within `tolerance` of the curve.
+    fn chords(&self, tolerance: <Self::Point as Point2D>::Value) -> usize;
+
+    /// Returns the curve rotated around the origin by `rotation`, then translated by `offset`,
+    /// the same transformation a polygon applies to its local vertices.
+    fn transform(&self, rotation: <Self::Point as Point2D>::Value, offset: Self::Point) -> Self;
+
+    /// Returns the curve reflected across the y axis, the reflection of a mirrored polygon.
+    fn reflect(&self) -> Self;
+
+    /// Returns the same curve traversed from end to start.
+    fn reversed(&self) -> Self;
+
+    /// Returns points along the curve no further than `tolerance` from it,
+    /// excluding the start and including the end.
+    fn flatten(&self, tolerance: <Self::Point as Point2D>::Value) -> Vec<Self::Point> {
+        let chords = self.chords(tolerance).max(1);
+        let n = <<Self::Point as Point2D>::Value as NumCast>::from(chords).unwrap();
+        (1..chords)
+            .map(|i| {
+                let t = <<Self::Point as Point2D>::Value as NumCast>::from(i).unwrap() / n;
+                self.point_at(t)
+            })
+            .chain(std::iter::once(self.end()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Point2D> Line<T> {
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+}
+
+impl<T: Point2D> Curve for Line<T> {
+    type Point = T;
+
+    fn start(&self) -> T {
+        self.start
+    }
+
+    fn end(&self) -> T {
+        self.end
+    }
+
+    fn point_at(&self, t: T::Value) -> T {
+        self.start + (self.end - self.start) * t
+    }
+
+    fn chords(&self, _tolerance: T::Value) -> usize {
+        1
+    }
+
+    fn transform(&self, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.start.rotate(rotation) + offset,
+            self.end.rotate(rotation) + offset,
+        )
+    }
+
+    fn reflect(&self) -> Self {
+        Self::new(self.start.reflect(), self.end.reflect())
+    }
+
+    fn reversed(&self) -> Self {
+        Self::new(self.end, self.start)
+    }
+}
+
+/// A circular arc from `start_angle` sweeping `sweep` radians,
+/// counter-clockwise for a positive sweep and clockwise for a negative sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircularArc<T: Point2D> {
+    pub center: T,
+    pub radius: T::Value,
+    pub start_angle: T::Value,
+    pub sweep: T::Value,
+}
+
+impl<T: Point2D> CircularArc<T> {
+    pub fn new(center: T, radius: T::Value, start_angle: T::Value, sweep: T::Value) -> Self {
+        Self {
+            center,
+            radius,
+            start_angle,
+            sweep,
+        }
+    }
+
+    /// Returns the full circle starting and ending at angle zero.
+    pub fn circle(center: T, radius: T::Value) -> Self
+    where
+        T::Value: FloatConst,
+    {
+        Self::new(center, radius, Zero::zero(), T::Value::TAU())
+    }
+
+    /// Returns the arc from `start` to `end` with the given bulge, the tangent of a quarter of
+    /// the sweep as used by DXF polylines. A positive bulge sweeps counter-clockwise.
+    /// Returns None for a zero bulge or coinciding points, which are straight lines.
+    pub fn from_bulge(start: T, end: T, bulge: T::Value) -> Option<Self> {
+        let one = T::Value::one();
+        let two = one + one;
+        let chord = end - start;
+        let length = chord.dot(&chord).sqrt();
+        if bulge == Zero::zero() || length == Zero::zero() {
+            return None;
+        }
+        let sweep = (two + two) * bulge.atan();
+        // distance from the middle of the chord to the center, towards the left for a positive bulge
+        let apothem = length * (one - bulge * bulge) / (two * two * bulge);
+        let normal = T::from_xy(-chord.y(), chord.x()) / length;
+        let center = (start + end) / two + normal * apothem;
+        let radius = length * (one + bulge * bulge) / (two * two * bulge.abs());
+        let start_angle = (start.y() - center.y()).atan2(start.x() - center.x());
+        Some(Self::new(center, radius, start_angle, sweep))
+    }
+
+    fn point_at_angle(&self, angle: T::Value) -> T {
+        self.center + T::from_xy(angle.cos(), angle.sin()) * self.radius
+    }
+}
+
+impl<T: Point2D> Curve for CircularArc<T>
+where
+    T::Value: FloatConst,
+{
+    type Point = T;
+
+    fn start(&self) -> T {
+        self.point_at_angle(self.start_angle)
+    }
+
+    fn end(&self) -> T {
+        self.point_at_angle(self.start_angle + self.sweep)
+    }
+
+    fn point_at(&self, t: T::Value) -> T {
+        self.point_at_angle(self.start_angle + self.sweep * t)
+    }
+
+    fn chords(&self, tolerance: T::Value) -> usize {
+        let one = T::Value::one();
+        let two = one + one;
+        // a chord spanning the angle 2 acos(1 - tolerance / radius) deviates exactly by the tolerance,
+        // with at least three chords to a full circle
+        let ratio = (tolerance / self.radius).min(one).max(Zero::zero());
+        let max_step = two * T::Value::PI() / (two + one);
+        let step = (two * (one - ratio).acos()).min(max_step);
+        if step <= Zero::zero() {
+            return 1;
+        }
+        (self.sweep.abs() / step)
+            .ceil()
+            .to_usize()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    fn transform(&self, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.center.rotate(rotation) + offset,
+            self.radius,
+            self.start_angle + rotation,
+            self.sweep,
+        )
+    }
+
+    fn reflect(&self) -> Self {
+        // the angle a of a point becomes pi - a, so the arc sweeps the other way
+        Self::new(
+            self.center.reflect(),
+            self.radius,
+            T::Value::PI() - self.start_angle,
+            -self.sweep,
+        )
+    }
+
+    fn reversed(&self) -> Self {
+        Self::new(
+            self.center,
+            self.radius,
+            self.start_angle + self.sweep,
+            -self.sweep,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadraticBezier<T> {
+    pub start: T,
+    pub control: T,
+    pub end: T,
+}
+
+impl<T: Point2D> QuadraticBezier<T> {
+    pub fn new(start: T, control: T, end: T) -> Self {
+        Self {
+            start,
+            control,
+            end,
+        }
+    }
+}
+
+impl<T: Point2D> Curve for QuadraticBezier<T> {
+    type Point = T;
+
+    fn start(&self) -> T {
+        self.start
+    }
+
+    fn end(&self) -> T {
+        self.end
+    }
+
+    fn point_at(&self, t: T::Value) -> T {
+        let one = T::Value::one();
+        let s = one - t;
+        self.start * (s * s) + self.control * ((one + one) * s * t) + self.end * (t * t)
+    }
+
+    fn chords(&self, tolerance: T::Value) -> usize {
+        // chords of length h in t deviate at most h^2 / 8 * |B''| with |B''| = 2 |p0 - 2 p1 + p2|
+        let one = T::Value::one();
+        let two = one + one;
+        let second = self.start - self.control * two + self.end;
+        let bound = second.dot(&second).sqrt() / (two * two);
+        chords_for_bound(bound, tolerance)
+    }
+
+    fn transform(&self, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.start.rotate(rotation) + offset,
+            self.control.rotate(rotation) + offset,
+            self.end.rotate(rotation) + offset,
+        )
+    }
+
+    fn reflect(&self) -> Self {
+        Self::new(
+            self.start.reflect(),
+            self.control.reflect(),
+            self.end.reflect(),
+        )
+    }
+
+    fn reversed(&self) -> Self {
+        Self::new(self.end, self.control, self.start)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CubicBezier<T> {
+    pub start: T,
+    pub control1: T,
+    pub control2: T,
+    pub end: T,
+}
+
+impl<T: Point2D> CubicBezier<T> {
+    pub fn new(start: T, control1: T, control2: T, end: T) -> Self {
+        Self {
+            start,
+            control1,
+            control2,
+            end,
+        }
+    }
+}
+
+impl<T: Point2D> Curve for CubicBezier<T> {
+    type Point = T;
+
+    fn start(&self) -> T {
+        self.start
+    }
+
+    fn end(&self) -> T {
+        self.end
+    }
+
+    fn point_at(&self, t: T::Value) -> T {
+        let one = T::Value::one();
+        let three = one + one + one;
+        let s = one - t;
+        self.start * (s * s * s)
+            + self.control1 * (three * s * s * t)
+            + self.control2 * (three * s * t * t)
+            + self.end * (t * t * t)
+    }
+
+    fn chords(&self, tolerance: T::Value) -> usize {
+        // |B''| is at most 6 times the larger second difference of the control points,
+        // so chords of length h in t deviate at most 3 / 4 h^2 of it
+        let one = T::Value::one();
+        let two = one + one;
+        let first = self.start - self.control1 * two + self.control2;
+        let second = self.control1 - self.control2 * two + self.end;
+        let largest = first.dot(&first).max(second.dot(&second)).sqrt();
+        chords_for_bound(largest * (two + one) / (two * two), tolerance)
+    }
+
+    fn transform(&self, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.start.rotate(rotation) + offset,
+            self.control1.rotate(rotation) + offset,
+            self.control2.rotate(rotation) + offset,
+            self.end.rotate(rotation) + offset,
+        )
+    }
+
+    fn reflect(&self) -> Self {
+        Self::new(
+            self.start.reflect(),
+            self.control1.reflect(),
+            self.control2.reflect(),
+            self.end.reflect(),
+        )
+    }
+
+    fn reversed(&self) -> Self {
+        Self::new(self.end, self.control2, self.control1, self.start)
+    }
+}
+
+/// Returns n so that bound / n^2 is at most the tolerance.
+fn chords_for_bound<T: Float>(bound: T, tolerance: T) -> usize {
+    if tolerance <= T::zero() {
+        return 1;
+    }
+    (bound / tolerance)
+        .sqrt()
+        .ceil()
+        .to_usize()
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Any of the supported segment types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveSegment<T: Point2D> {
+    Line(Line<T>),
+    Arc(CircularArc<T>),
+    Quadratic(QuadraticBezier<T>),
+    Cubic(CubicBezier<T>),
+}
+
+impl<T: Point2D> Curve for CurveSegment<T>
+where
+    T::Value: FloatConst,
+{
+    type Point = T;
+
+    fn start(&self) -> T {
+        match self {
+            CurveSegment::Line(line) => line.start(),
+            CurveSegment::Arc(arc) => arc.start(),
+            CurveSegment::Quadratic(bezier) => bezier.start(),
+            CurveSegment::Cubic(bezier) => bezier.start(),
+        }
+    }
+
+    fn end(&self) -> T {
+        match self {
+            CurveSegment::Line(line) => line.end(),
+            CurveSegment::Arc(arc) => arc.end(),
+            CurveSegment::Quadratic(bezier) => bezier.end(),
+            CurveSegment::Cubic(bezier) => bezier.end(),
+        }
+    }
+
+    fn point_at(&self, t: T::Value) -> T {
+        match self {
+            CurveSegment::Line(line) => line.point_at(t),
+            CurveSegment::Arc(arc) => arc.point_at(t),
+            CurveSegment::Quadratic(bezier) => bezier.point_at(t),
+            CurveSegment::Cubic(bezier) => bezier.point_at(t),
+        }
+    }
+
+    fn chords(&self, tolerance: T::Value) -> usize {
+        match self {
+            CurveSegment::Line(line) => line.chords(tolerance),
+            CurveSegment::Arc(arc) => arc.chords(tolerance),
+            CurveSegment::Quadratic(bezier) => bezier.chords(tolerance),
+            CurveSegment::Cubic(bezier) => bezier.chords(tolerance),
+        }
+    }
+
+    fn transform(&self, rotation: T::Value, offset: T) -> Self {
+        match self {
+            CurveSegment::Line(line) => CurveSegment::Line(line.transform(rotation, offset)),
+            CurveSegment::Arc(arc) => CurveSegment::Arc(arc.transform(rotation, offset)),
+            CurveSegment::Quadratic(bezier) => {
+                CurveSegment::Quadratic(bezier.transform(rotation, offset))
+            }
+            CurveSegment::Cubic(bezier) => CurveSegment::Cubic(bezier.transform(rotation, offset)),
+        }
+    }
+
+    fn reflect(&self) -> Self {
+        match self {
+            CurveSegment::Line(line) => CurveSegment::Line(line.reflect()),
+            CurveSegment::Arc(arc) => CurveSegment::Arc(arc.reflect()),
+            CurveSegment::Quadratic(bezier) => CurveSegment::Quadratic(bezier.reflect()),
+            CurveSegment::Cubic(bezier) => CurveSegment::Cubic(bezier.reflect()),
+        }
+    }
+
+    fn reversed(&self) -> Self {
+        match self {
+            CurveSegment::Line(line) => CurveSegment::Line(line.reversed()),
+            CurveSegment::Arc(arc) => CurveSegment::Arc(arc.reversed()),
+            CurveSegment::Quadratic(bezier) => CurveSegment::Quadratic(bezier.reversed()),
+            CurveSegment::Cubic(bezier) => CurveSegment::Cubic(bezier.reversed()),
+        }
+    }
+}
+
+impl<T: Point2D> From<Line<T>> for CurveSegment<T> {
+    fn from(line: Line<T>) -> Self {
+        CurveSegment::Line(line)
+    }
+}
+
+impl<T: Point2D> From<CircularArc<T>> for CurveSegment<T> {
+    fn from(arc: CircularArc<T>) -> Self {
+        CurveSegment::Arc(arc)
+    }
+}
+
+impl<T: Point2D> From<QuadraticBezier<T>> for CurveSegment<T> {
+    fn from(bezier: QuadraticBezier<T>) -> Self {
+        CurveSegment::Quadratic(bezier)
+    }
+}
+
+impl<T: Point2D> From<CubicBezier<T>> for CurveSegment<T> {
+    fn from(bezier: CubicBezier<T>) -> Self {
+        CurveSegment::Cubic(bezier)
+    }
+}
+
+/// A closed contour made of segments, each starting where the previous one ends.
+/// The last segment ends at the start of the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvedContour<T: Point2D> {
+    pub segments: Vec<CurveSegment<T>>,
+}
+
+impl<T: Point2D> CurvedContour<T>
+where
+    T::Value: FloatConst,
+{
+    pub fn new(segments: Vec<CurveSegment<T>>) -> Self {
+        Self { segments }
+    }
+
+    /// Returns the closed polygon through the given vertices.
+    pub fn from_vertices(vertices: &[T]) -> Self {
+        Self::new(
+            (0..vertices.len())
+                .map(|i| Line::new(vertices[i], vertices[(i + 1) % vertices.len()]).into())
+                .collect(),
+        )
+    }
+
+    /// Returns the vertices of a polygon within `tolerance` of the contour,
+    /// starting at the start of the first segment.
+    pub fn flatten(&self, tolerance: T::Value) -> Vec<T> {
+        let Some(first) = self.segments.first() else {
+            return vec![];
+        };
+        let mut vertices = vec![first.start()];
+        for segment in self.segments.iter() {
+            vertices.extend(segment.flatten(tolerance));
+        }
+        // the last segment closes the contour
+        vertices.pop();
+        vertices
+    }
+
+    /// Returns the polygon within `tolerance` of the contour.
+    pub fn to_polygon<P>(&self, tolerance: T::Value) -> P
+    where
+        P: Polygon<Point = T> + From<Vec<T>>,
+    {
+        P::from(self.flatten(tolerance))
+    }
+
+    /// Returns the contour reflected across the y axis if `mirrored`,
+    /// then rotated around the origin by `rotation` and translated by `offset`.
+    pub fn transform(&self, mirrored: bool, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.segments
+                .iter()
+                .map(|segment| {
+                    if mirrored {
+                        segment.reflect().transform(rotation, offset)
+                    } else {
+                        segment.transform(rotation, offset)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the contour traversed in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        Self::new(
+            self.segments
+                .iter()
+                .rev()
+                .map(|segment| segment.reversed())
+                .collect(),
+        )
+    }
+}
+
+/// The curved counterpart of a multi polygon, an outer contour with holes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvedMultiPolygon<T: Point2D> {
+    pub outer: CurvedContour<T>,
+    pub holes: Vec<CurvedContour<T>>,
+}
+
+impl<T: Point2D> CurvedMultiPolygon<T>
+where
+    T::Value: FloatConst,
+{
+    pub fn new(outer: CurvedContour<T>, holes: Vec<CurvedContour<T>>) -> Self {
+        Self { outer, holes }
+    }
+
+    /// Returns the multi polygon within `tolerance` of the curves.
+    pub fn to_multi_polygon<P>(&self, tolerance: T::Value) -> MultiPolygon<P>
+    where
+        P: Polygon<Point = T> + From<Vec<T>>,
+    {
+        MultiPolygon::new(
+            self.outer.to_polygon(tolerance),
+            self.holes
+                .iter()
+                .map(|hole| hole.to_polygon(tolerance))
+                .collect(),
+        )
+    }
+
+    /// Returns the curves moved like a multi polygon with the given mirroring, rotation and offset,
+    /// eg. to export a placed piece with its original curves.
+    pub fn transform(&self, mirrored: bool, rotation: T::Value, offset: T) -> Self {
+        Self::new(
+            self.outer.transform(mirrored, rotation, offset),
+            self.holes
+                .iter()
+                .map(|hole| hole.transform(mirrored, rotation, offset))
+                .collect(),
+        )
+    }
+}
+
+/// Groups closed contours into multi polygons, a contour directly inside of an outer contour
+/// is a hole of it and a contour inside of a hole is the outer contour of another multi polygon.
+/// Containment is decided on the contours flattened with `tolerance`.
+pub(crate) fn nest_curved_contours<T>(
+    loops: Vec<CurvedContour<T>>,
+    tolerance: T::Value,
+) -> Vec<CurvedMultiPolygon<T>>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let flattened: Vec<Vec<T>> = loops.iter().map(|l| l.flatten(tolerance)).collect();
+    let areas: Vec<T::Value> = flattened
+        .iter()
+        .map(|vertices| signed_area(vertices).abs())
+        .collect();
+    let inside = |inner: usize, outer: usize| {
+        areas[inner] < areas[outer]
+            && flattened[inner]
+                .iter()
+                .any(|vertex| point_in_contour(*vertex, &flattened[outer]))
+    };
+    // the smallest loop containing each loop
+    let parents: Vec<Option<usize>> = (0..loops.len())
+        .map(|i| {
+            (0..loops.len())
+                .filter(|j| *j != i && inside(i, *j))
+                .min_by(|a, b| areas[*a].partial_cmp(&areas[*b]).unwrap())
+        })
+        .collect();
+    let depth = |mut i: usize| {
+        let mut depth = 0;
+        while let Some(parent) = parents[i] {
+            depth += 1;
+            i = parent;
+        }
+        depth
+    };
+
+    let mut pieces: Vec<(usize, CurvedMultiPolygon<T>)> = vec![];
+    for i in (0..loops.len()).filter(|i| depth(*i) % 2 == 0) {
+        pieces.push((i, CurvedMultiPolygon::new(loops[i].clone(), vec![])));
+    }
+    for i in (0..loops.len()).filter(|i| depth(*i) % 2 == 1) {
+        let parent = parents[i].unwrap();
+        if let Some((_, piece)) = pieces.iter_mut().find(|(j, _)| *j == parent) {
+            piece.holes.push(loops[i].clone());
+        }
+    }
+    pieces.into_iter().map(|(_, piece)| piece).collect()
+}
+
+fn signed_area<T: Point2D>(vertices: &[T]) -> T::Value {
+    let two = T::Value::one() + T::Value::one();
+    (0..vertices.len())
+        .map(|i| {
+            let a = vertices[i];
+            let b = vertices[(i + 1) % vertices.len()];
+            (a.x() + b.x()) * (a.y() - b.y())
+        })
+        .fold(T::Value::zero(), |sum, x| sum + x)
+        / two
+}
+
+/// Even-odd test whether the point is strictly inside of the contour.
+fn point_in_contour<T: Point2D>(point: T, contour: &[T]) -> bool {
+    let mut inside = false;
+    for i in 0..contour.len() {
+        let a = contour[i];
+        let b = contour[(i + 1) % contour.len()];
+        if (a.y() > point.y()) != (b.y() > point.y()) {
+            let x = a.x() + (point.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x());
+            if point.x() < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{Point2D, Polygon};
+    use crate::point::Point2D as _;
+    use crate::polygon::Polygon as _;
+    use std::f64::consts::PI;
+
+    /// Returns the largest distance between the curve and the polyline through its flattened points.
+    fn deviation<C: Curve<Point = Point2D>>(curve: &C, tolerance: f64) -> f64 {
+        let mut polyline = vec![curve.start()];
+        polyline.extend(curve.flatten(tolerance));
+        (0..=1000)
+            .map(|i| {
+                let point = curve.point_at(i as f64 / 1000.0);
+                polyline
+                    .windows(2)
+                    .map(|w| {
+                        let edge = w[1] - w[0];
+                        let t = ((point - w[0]).dot(&edge) / edge.dot(&edge)).clamp(0.0, 1.0);
+                        let closest = w[0] + edge * t;
+                        (point - closest).dot(&(point - closest)).sqrt()
+                    })
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_flatten_stays_within_tolerance() {
+        let arc = CircularArc::new(Point2D { x: 1.0, y: 2.0 }, 10.0, 0.3, -2.0);
+        let quadratic = QuadraticBezier::new(
+            Point2D { x: 0.0, y: 0.0 },
+            Point2D { x: 5.0, y: 10.0 },
+            Point2D { x: 10.0, y: 0.0 },
+        );
+        let cubic = CubicBezier::new(
+            Point2D { x: 0.0, y: 0.0 },
+            Point2D { x: 0.0, y: 10.0 },
+            Point2D { x: 10.0, y: -10.0 },
+            Point2D { x: 10.0, y: 0.0 },
+        );
+        for tolerance in [1.0, 0.1, 0.01] {
+            assert!(deviation(&arc, tolerance) <= tolerance + 1e-9);
+            assert!(deviation(&quadratic, tolerance) <= tolerance + 1e-9);
+            assert!(deviation(&cubic, tolerance) <= tolerance + 1e-9);
+        }
+        assert!(arc.flatten(0.01).len() > arc.flatten(1.0).len());
+        approx::assert_abs_diff_eq!(*arc.flatten(0.1).last().unwrap(), arc.end(), epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_arc_from_bulge() {
+        // a bulge of 1 is a half circle, counter-clockwise from (0, 0) to (2, 0) passes below
+        let arc =
+            CircularArc::from_bulge(Point2D { x: 0.0, y: 0.0 }, Point2D { x: 2.0, y: 0.0 }, 1.0)
+                .unwrap();
+        approx::assert_abs_diff_eq!(arc.center, Point2D { x: 1.0, y: 0.0 }, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(arc.radius, 1.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(
+            arc.point_at(0.5),
+            Point2D { x: 1.0, y: -1.0 },
+            epsilon = 1e-9
+        );
+        approx::assert_abs_diff_eq!(arc.end(), Point2D { x: 2.0, y: 0.0 }, epsilon = 1e-9);
+        assert!(CircularArc::from_bulge(arc.start(), arc.end(), 0.0).is_none());
+    }
+
+    #[test]
+    fn test_contour_to_polygon() {
+        // a slot: two half circles joined by straight lines
+        let contour = CurvedContour::new(vec![
+            Line::new(Point2D { x: 0.0, y: -1.0 }, Point2D { x: 4.0, y: -1.0 }).into(),
+            CircularArc::new(Point2D { x: 4.0, y: 0.0 }, 1.0, -PI / 2.0, PI).into(),
+            Line::new(Point2D { x: 4.0, y: 1.0 }, Point2D { x: 0.0, y: 1.0 }).into(),
+            CircularArc::new(Point2D { x: 0.0, y: 0.0 }, 1.0, PI / 2.0, PI).into(),
+        ]);
+        let polygon: Polygon = contour.to_polygon(0.001);
+        // counter-clockwise has a negative area
+        approx::assert_abs_diff_eq!(polygon.area(), -(8.0 + PI), epsilon = 0.01);
+        assert!(polygon.area() > -(8.0 + PI));
+        approx::assert_abs_diff_eq!(
+            polygon.iter_vertices().next().unwrap(),
+            Point2D { x: 0.0, y: -1.0 }
+        );
+
+        let reversed: Polygon = contour.reversed().to_polygon(0.001);
+        approx::assert_abs_diff_eq!(reversed.area(), -polygon.area(), epsilon = 1e-9);
+
+        let circle = CurvedMultiPolygon::new(
+            CurvedContour::new(vec![
+                CircularArc::circle(Point2D { x: 0.0, y: 0.0 }, 5.0).into()
+            ]),
+            vec![CurvedContour::from_vertices(&[
+                Point2D { x: -1.0, y: -1.0 },
+                Point2D { x: 1.0, y: -1.0 },
+                Point2D { x: 1.0, y: 1.0 },
+                Point2D { x: -1.0, y: 1.0 },
+            ])],
+        );
+        let multi_polygon = circle.to_multi_polygon::<Polygon>(0.001);
+        approx::assert_abs_diff_eq!(multi_polygon.area(), 25.0 * PI - 4.0, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_transform_matches_polygon_transform() {
+        let contour = CurvedContour::new(vec![
+            CubicBezier::new(
+                Point2D { x: 0.0, y: 0.0 },
+                Point2D { x: 2.0, y: 3.0 },
+                Point2D { x: 4.0, y: 3.0 },
+                Point2D { x: 6.0, y: 0.0 },
+            )
+            .into(),
+            CircularArc::new(Point2D { x: 3.0, y: 0.0 }, 3.0, 0.0, -PI).into(),
+        ]);
+        let offset = Point2D { x: 10.0, y: -5.0 };
+        let mut polygon: Polygon = contour.to_polygon(0.02);
+        polygon.set_rotation(1.0);
+        polygon.set_offset(offset);
+        let transformed: Polygon = contour.transform(false, 1.0, offset).to_polygon(0.02);
+        assert_eq!(polygon.length(), transformed.length());
+        for (a, b) in polygon.iter_vertices().zip(transformed.iter_vertices()) {
+            approx::assert_abs_diff_eq!(a, b, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_transform_matches_mirrored_placement() {
+        use crate::nesting::problem::IrregularBinPackingPlacement;
+
+        let piece = CurvedMultiPolygon::new(
+            CurvedContour::new(vec![
+                QuadraticBezier::new(
+                    Point2D { x: 0.0, y: 0.0 },
+                    Point2D { x: 3.0, y: -2.0 },
+                    Point2D { x: 6.0, y: 0.0 },
+                )
+                .into(),
+                CircularArc::new(Point2D { x: 3.0, y: 0.0 }, 3.0, 0.0, PI).into(),
+            ]),
+            vec![CurvedContour::new(vec![CircularArc::circle(
+                Point2D { x: 4.0, y: 1.0 },
+                0.5,
+            )
+            .into()])],
+        );
+        let mut placement =
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 10.0, y: -5.0 }, 1.0);
+        placement.set_mirrored(true);
+
+        let placed = placement.place_piece(&piece.to_multi_polygon::<Polygon>(0.02));
+        let transformed = piece
+            .transform(
+                placement.mirrored(),
+                placement.rotation(),
+                placement.location(),
+            )
+            .to_multi_polygon::<Polygon>(0.02);
+        assert_eq!(transformed.holes().len(), 1);
+        for (a, b) in placed.contours().iter().zip(transformed.contours()) {
+            assert_eq!(a.1, b.1);
+            assert_eq!(a.0.len(), b.0.len());
+            for (a, b) in a.0.iter().zip(b.0.iter()) {
+                approx::assert_abs_diff_eq!(a, b, epsilon = 1e-9);
+            }
+        }
+    }
+}
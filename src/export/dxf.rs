@@ -0,0 +1,356 @@
+//! Writes packing solutions as DXF for cutting machines
+//!
+//! Every contour of a placed piece is a closed LWPOLYLINE, outer contours on the
+//! [`OUTER_LAYER`] layer and holes on the [`HOLE_LAYER`] layer, so machines can cut
+//! holes before the outer contours. The outline of the bin is on the [`BIN_LAYER`] layer.
+//!
+//! The drawing is an R2000 file, every table record and entity has a handle and subclass
+//! markers. It holds only the header, the layer table and the entities, which is enough for
+//! cutting and CAM software. Readers requiring every table and object of an R2000 drawing,
+//! like AutoCAD, may refuse it.
+
+use crate::multi_polygon::MultiPolygon;
+use crate::nesting::problem::{IrregularBinPackingProblem, IrregularBinPackingSolution};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::ToPrimitive;
+use std::io::Write;
+
+pub const OUTER_LAYER: &str = "OUTER";
+pub const HOLE_LAYER: &str = "HOLES";
+pub const BIN_LAYER: &str = "BIN";
+
+/// Handle of the layer table, its records follow it and the entities start at
+/// [`FIRST_ENTITY_HANDLE`]
+const LAYER_TABLE_HANDLE: usize = 1;
+const FIRST_ENTITY_HANDLE: usize = 0x10;
+
+/// The entities of a drawing and the handle of the next one
+struct Entities {
+    text: String,
+    next_handle: usize,
+}
+
+impl Entities {
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            next_handle: FIRST_ENTITY_HANDLE,
+        }
+    }
+
+    /// Starts an entity of `kind` on `layer`, the subclass marker of the kind is left to the caller
+    fn start(&mut self, kind: &str, layer: &str) {
+        self.text += &format!(
+            "0\n{}\n5\n{:X}\n100\nAcDbEntity\n8\n{}\n",
+            kind, self.next_handle, layer
+        );
+        self.next_handle += 1;
+    }
+}
+
+/// Writes the pieces placed in one bin.
+pub fn write_bin<P: Polygon, W: Write>(
+    writer: &mut W,
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+    bin_id: usize,
+) -> Result<()> {
+    if bin_id >= solution.bins_used() {
+        return Err(anyhow!("The solution uses no bin {}", bin_id));
+    }
+    let mut entities = Entities::new();
+    write_bin_entities(&mut entities, problem, solution, bin_id, 0.0);
+    write_document(writer, &entities)
+}
+
+/// Writes the pieces of every bin into a single drawing, the bins side by side
+/// with at least `gap` between them.
+pub fn write_solution<P: Polygon, W: Write>(
+    writer: &mut W,
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+    gap: <P::Point as Point2D>::Value,
+) -> Result<()> {
+    // the widest bin type
+    let width = problem
+        .bin_descriptions()
+        .iter()
+        .map(|description| {
+            let bin_box = description.bin.bounding_box();
+            (bin_box.max_x - bin_box.min_x).to_f64().unwrap()
+        })
+        .fold(0.0, f64::max);
+    let stride = width + gap.to_f64().unwrap();
+    let mut entities = Entities::new();
+    for bin_id in 0..solution.bins_used() {
+        // every bin starts at its slot, wherever its own coordinates start
+        let bin_box = problem.bin_descriptions()[solution.bin_type(bin_id)]
+            .bin
+            .bounding_box();
+        write_bin_entities(
+            &mut entities,
+            problem,
+            solution,
+            bin_id,
+            stride * bin_id as f64 - bin_box.min_x.to_f64().unwrap(),
+        );
+    }
+    write_document(writer, &entities)
+}
+
+fn write_bin_entities<P: Polygon>(
+    entities: &mut Entities,
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+    bin_id: usize,
+    offset_x: f64,
+) {
+    let bin = &problem.bin_descriptions()[solution.bin_type(bin_id)].bin;
+    write_multi_polygon(entities, bin, BIN_LAYER, BIN_LAYER, offset_x);
+    for placement in solution
+        .placements()
+        .iter()
+        .filter(|p| p.bin_id() == bin_id)
+    {
+        let piece =
+            placement.place_piece(&problem.piece_descriptions()[placement.piece_id()].piece);
+        write_multi_polygon(entities, &piece, OUTER_LAYER, HOLE_LAYER, offset_x);
+    }
+}
+
+fn write_multi_polygon<P: Polygon>(
+    entities: &mut Entities,
+    multi_polygon: &MultiPolygon<P>,
+    outer_layer: &str,
+    hole_layer: &str,
+    offset_x: f64,
+) {
+    for (contour, hole) in multi_polygon.contours() {
+        let layer = if hole { hole_layer } else { outer_layer };
+        entities.start("LWPOLYLINE", layer);
+        entities.text += &format!("100\nAcDbPolyline\n90\n{}\n70\n1\n", contour.len());
+        for point in contour.iter() {
+            entities.text += &format!(
+                "10\n{}\n20\n{}\n",
+                point.x().to_f64().unwrap() + offset_x,
+                point.y().to_f64().unwrap()
+            );
+        }
+    }
+}
+
+fn write_document<W: Write>(writer: &mut W, entities: &Entities) -> Result<()> {
+    write!(
+        writer,
+        "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1015\n9\n$HANDSEED\n5\n{:X}\n0\nENDSEC\n",
+        entities.next_handle
+    )?;
+    write!(
+        writer,
+        "0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n5\n{:X}\n100\nAcDbSymbolTable\n70\n3\n",
+        LAYER_TABLE_HANDLE
+    )?;
+    for (i, (layer, color)) in [(OUTER_LAYER, 7), (HOLE_LAYER, 1), (BIN_LAYER, 8)]
+        .into_iter()
+        .enumerate()
+    {
+        write!(
+            writer,
+            "0\nLAYER\n5\n{:X}\n330\n{:X}\n100\nAcDbSymbolTableRecord\n100\nAcDbLayerTableRecord\n\
+             2\n{}\n70\n0\n62\n{}\n6\nCONTINUOUS\n",
+            LAYER_TABLE_HANDLE + 1 + i,
+            LAYER_TABLE_HANDLE,
+            layer,
+            color
+        )?;
+    }
+    write!(writer, "0\nENDTAB\n0\nENDSEC\n")?;
+    write!(
+        writer,
+        "0\nSECTION\n2\nENTITIES\n{}0\nENDSEC\n0\nEOF\n",
+        entities.text
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{rectangle, rectangle_at, Point2D, Polygon};
+    use crate::nesting::problem::{BinDescription, IrregularBinPackingPlacement};
+    use crate::parsers::dxf::parse_dxf;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn problem() -> IrregularBinPackingProblem<Polygon> {
+        let frame = MultiPolygon::new(
+            rectangle(4.0, 4.0).outer().clone(),
+            vec![Polygon::from_tuples([
+                (1.0, 1.0),
+                (3.0, 1.0),
+                (3.0, 3.0),
+                (1.0, 3.0),
+            ])],
+        );
+        IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(frame, vec![0.0, FRAC_PI_2], 3)
+            .build()
+            .unwrap()
+    }
+
+    /// Returns the layer and vertices of every polyline.
+    fn polylines(dxf: &str) -> Vec<(String, Vec<(f64, f64)>)> {
+        let lines: Vec<&str> = dxf.lines().collect();
+        let mut polylines: Vec<(String, Vec<(f64, f64)>)> = vec![];
+        for pair in lines.chunks(2) {
+            match (pair[0], pair[1]) {
+                ("0", "LWPOLYLINE") => polylines.push((String::new(), vec![])),
+                ("8", layer) => polylines.last_mut().unwrap().0 = layer.to_string(),
+                ("10", x) => polylines
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push((x.parse().unwrap(), 0.0)),
+                ("20", y) => {
+                    polylines.last_mut().unwrap().1.last_mut().unwrap().1 = y.parse().unwrap()
+                }
+                _ => {}
+            }
+        }
+        polylines
+    }
+
+    #[test]
+    fn test_handles_and_subclass_markers() {
+        let problem = problem();
+        let solution = IrregularBinPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 6.0, y: 6.0 }, 0.0),
+        ]);
+        let mut dxf = vec![];
+        write_bin(&mut dxf, &problem, &solution, 0).unwrap();
+        let dxf = String::from_utf8(dxf).unwrap();
+        let lines: Vec<&str> = dxf.lines().collect();
+
+        // every object with the groups following its 0 group
+        let mut objects: Vec<(&str, Vec<(&str, &str)>)> = vec![];
+        let mut seed = None;
+        for pair in lines.chunks(2) {
+            match (pair[0], pair[1]) {
+                ("0", kind) => objects.push((kind, vec![])),
+                ("5", handle) if objects.last().map(|o| o.0) == Some("SECTION") => {
+                    seed = Some(usize::from_str_radix(handle, 16).unwrap())
+                }
+                group => objects.last_mut().unwrap().1.push(group),
+            }
+        }
+        let required = [
+            ("TABLE", vec!["AcDbSymbolTable"]),
+            (
+                "LAYER",
+                vec!["AcDbSymbolTableRecord", "AcDbLayerTableRecord"],
+            ),
+            ("LWPOLYLINE", vec!["AcDbEntity", "AcDbPolyline"]),
+        ];
+        let mut handles = vec![];
+        for (kind, groups) in objects.iter() {
+            let Some((_, markers)) = required.iter().find(|(k, _)| k == kind) else {
+                continue;
+            };
+            let found: Vec<&str> = groups
+                .iter()
+                .filter(|(code, _)| *code == "100")
+                .map(|(_, marker)| *marker)
+                .collect();
+            assert_eq!(&found, markers, "{}", kind);
+            let (_, handle) = groups.iter().find(|(code, _)| *code == "5").unwrap();
+            handles.push(usize::from_str_radix(handle, 16).unwrap());
+        }
+        // the layer table, three layers, the bin and two frames with their holes
+        assert_eq!(handles.len(), 1 + 3 + 5);
+        let seed = seed.unwrap();
+        for (i, handle) in handles.iter().enumerate() {
+            assert!(*handle > 0 && *handle < seed);
+            assert!(!handles[..i].contains(handle));
+        }
+    }
+
+    #[test]
+    fn test_write_bins() {
+        let problem = problem();
+        let solution = IrregularBinPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 5.0, y: 1.0 }, FRAC_PI_2),
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 6.0, y: 6.0 }, 0.0),
+            IrregularBinPackingPlacement::new(1, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+        ]);
+
+        let mut dxf = vec![];
+        write_bin(&mut dxf, &problem, &solution, 0).unwrap();
+        let dxf = String::from_utf8(dxf).unwrap();
+        let polylines = polylines(&dxf);
+        let count = |layer: &str| polylines.iter().filter(|(l, _)| l == layer).count();
+        assert_eq!(
+            (count(BIN_LAYER), count(OUTER_LAYER), count(HOLE_LAYER)),
+            (1, 2, 2)
+        );
+        // the rotated frame spans from x = 1 to 5
+        let (_, rotated) = polylines.iter().find(|(l, _)| l == OUTER_LAYER).unwrap();
+        let min_x = rotated.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        approx::assert_abs_diff_eq!(min_x, 1.0, epsilon = 1e-9);
+
+        // the pieces come back as holes of the bin, their holes as pieces of their own
+        let parsed = parse_dxf::<Polygon, _>(dxf.as_bytes(), 1e-6).unwrap();
+        let bin = parsed.iter().find(|p| p.holes().len() == 2).unwrap();
+        approx::assert_abs_diff_eq!(bin.area(), 100.0 - 32.0, epsilon = 1e-9);
+        assert!(write_bin(&mut vec![], &problem, &solution, 2).is_err());
+    }
+
+    #[test]
+    fn test_write_solution_side_by_side() {
+        let problem = problem();
+        let solution = IrregularBinPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+            IrregularBinPackingPlacement::new(1, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+        ]);
+        let mut dxf = vec![];
+        write_solution(&mut dxf, &problem, &solution, 5.0).unwrap();
+        let polylines = polylines(&String::from_utf8(dxf).unwrap());
+        let outers: Vec<_> = polylines.iter().filter(|(l, _)| l == OUTER_LAYER).collect();
+        assert_eq!(outers.len(), 2);
+        approx::assert_abs_diff_eq!(outers[1].1[0].0 - outers[0].1[0].0, 15.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_write_solution_bins_away_from_origin() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_descriptions([
+                BinDescription::new(rectangle_at(-3.0, 10.0, 10.0), None, 1.0),
+                BinDescription::new(rectangle_at(20.0, 5.0, 10.0), None, 1.0),
+            ])
+            .piece_descriptions(problem().piece_descriptions().clone())
+            .build()
+            .unwrap();
+        let solution = IrregularBinPackingSolution::with_bin_types(
+            vec![
+                IrregularBinPackingPlacement::new(0, 0, Point2D { x: -3.0, y: 0.0 }, 0.0),
+                IrregularBinPackingPlacement::new(1, 0, Point2D { x: 20.0, y: 0.0 }, 0.0),
+            ],
+            vec![0, 1],
+        );
+        let mut dxf = vec![];
+        write_solution(&mut dxf, &problem, &solution, 5.0).unwrap();
+        let polylines = polylines(&String::from_utf8(dxf).unwrap());
+        let min_x = |layer: &str| -> Vec<f64> {
+            polylines
+                .iter()
+                .filter(|(l, _)| l == layer)
+                .map(|(_, points)| points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min))
+                .collect()
+        };
+        // the widest bin is 10 wide, so the bins start 15 apart
+        assert_eq!(min_x(BIN_LAYER), [0.0, 15.0]);
+        assert_eq!(min_x(OUTER_LAYER), [0.0, 15.0]);
+    }
+}
@@ -0,0 +1,3 @@
+//! Writing problems and solutions to files for cutting machines or inspection.
+pub mod dxf;
+pub mod svg;
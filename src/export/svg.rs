@@ -0,0 +1,357 @@
+//! Writes problems, no fit polygons and solutions as SVG
+//!
+//! Coordinates are written as they are, at full precision and without flipping the y axis,
+//! so drawings read with [`crate::parsers::svg`] come back the way they were drawn.
+//! Every multi polygon is a single path with the holes as subpaths and an even-odd fill.
+
+use crate::bounding_box::BoundingBox;
+use crate::multi_polygon::MultiPolygon;
+use crate::nesting::problem::{IrregularBinPackingProblem, IrregularBinPackingSolution};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::Result;
+use num_traits::ToPrimitive;
+use std::io::Write;
+
+const BIN_STYLE: &str = r#"fill="none" stroke="black""#;
+const PIECE_STYLE: &str = r##"fill="#8ab4f8" fill-opacity="0.6" stroke="#1a3d7c""##;
+const NFP_STYLE: &str = r##"fill="#f28b82" fill-opacity="0.3" stroke="#a50e0e""##;
+
+/// Writes every bin type and every piece description side by side, the pieces to the right of the bins.
+/// Each bin is labelled with its bin type, quantity and cost,
+/// each piece with its piece id and number of instances.
+pub fn write_problem<P: Polygon, W: Write>(
+    writer: &mut W,
+    problem: &IrregularBinPackingProblem<P>,
+) -> Result<()> {
+    let bin_box = bins_bounds(problem);
+    let gap = gap(&bin_box);
+    let mut body = String::new();
+    let mut x = bin_box.min_x;
+    for (bin_type, description) in problem.bin_descriptions().iter().enumerate() {
+        let quantity = description
+            .quantity
+            .map_or("unlimited".to_string(), |quantity| quantity.to_string());
+        let type_box = bounds(description.bin.outer().iter_vertices());
+        body += &format!(
+            "<path class=\"bin\" data-bin-type=\"{}\" data-quantity=\"{}\" data-cost=\"{}\" d=\"{}\" fill-rule=\"evenodd\" transform=\"translate({} 0)\" {}><title>bin type {} x{} cost {}</title></path>\n",
+            bin_type,
+            quantity,
+            description.cost.to_f64().unwrap(),
+            multi_polygon_path(&description.bin, true),
+            x - type_box.min_x,
+            BIN_STYLE,
+            bin_type,
+            quantity,
+            description.cost.to_f64().unwrap(),
+        );
+        x += type_box.width() + gap;
+    }
+
+    let bin_box = BoundingBox {
+        min_x: bin_box.min_x,
+        min_y: bin_box.min_y,
+        max_x: x - gap,
+        max_y: bin_box.max_y,
+    };
+    let mut view = BoundingBox {
+        min_x: bin_box.min_x,
+        min_y: bin_box.min_y,
+        max_x: bin_box.max_x,
+        max_y: bin_box.max_y,
+    };
+    for (piece_id, description) in problem.piece_descriptions().iter().enumerate() {
+        let piece_box = bounds(description.piece.outer().iter_vertices_local().copied());
+        let (dx, dy) = (x - piece_box.min_x, bin_box.min_y - piece_box.min_y);
+        body += &format!(
+            "<path class=\"piece\" data-piece-id=\"{}\" data-instances=\"{}\" d=\"{}\" fill-rule=\"evenodd\" transform=\"translate({} {})\" {}><title>piece {} x{}</title></path>\n",
+            piece_id,
+            description.instances,
+            multi_polygon_path(&description.piece, false),
+            dx,
+            dy,
+            PIECE_STYLE,
+            piece_id,
+            description.instances,
+        );
+        view.max_x = x + piece_box.max_x - piece_box.min_x;
+        view.max_y = view
+            .max_y
+            .max(bin_box.min_y + piece_box.max_y - piece_box.min_y);
+        x = view.max_x + gap;
+    }
+    write_document(writer, &view, &body)
+}
+
+/// Writes no fit polygon contours, eg. the result of [`MultiPolygon::no_fit_polygon`].
+pub fn write_no_fit_polygons<T: Point2D, W: Write>(
+    writer: &mut W,
+    contours: &[Vec<T>],
+) -> Result<()> {
+    let view = bounds(contours.iter().flatten().copied());
+    let mut body = String::new();
+    for (i, contour) in contours.iter().enumerate() {
+        body += &format!(
+            "<path class=\"nfp\" data-contour=\"{}\" d=\"{}\" fill-rule=\"evenodd\" {}/>\n",
+            i,
+            contour_path(contour.iter().copied()),
+            NFP_STYLE
+        );
+    }
+    write_document(writer, &view, &body)
+}
+
+/// Writes one group per bin, the bins side by side. Each group holds the bin and
+/// the pieces placed in it, each piece a path in its own coordinates transformed to its placement.
+pub fn write_solution<P: Polygon, W: Write>(
+    writer: &mut W,
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+) -> Result<()> {
+    let bin_box = bins_bounds(problem);
+    let stride = bin_box.width() + gap(&bin_box);
+    let bins = solution.bins_used().max(1);
+    let mut body = String::new();
+    for bin_id in 0..bins {
+        let bin_type = solution.bin_type(bin_id);
+        body += &format!(
+            "<g class=\"bin\" id=\"bin-{}\" data-bin-id=\"{}\" data-bin-type=\"{}\" transform=\"translate({} 0)\">\n",
+            bin_id,
+            bin_id,
+            bin_type,
+            stride * bin_id as f64
+        );
+        body += &format!(
+            "<path d=\"{}\" fill-rule=\"evenodd\" {}/>\n",
+            multi_polygon_path(&problem.bin_descriptions()[bin_type].bin, true),
+            BIN_STYLE
+        );
+        for placement in solution
+            .placements()
+            .iter()
+            .filter(|p| p.bin_id() == bin_id)
+        {
+            let location = placement.location();
+            body += &format!(
+                "<path class=\"piece\" data-piece-id=\"{}\" d=\"{}\" fill-rule=\"evenodd\" transform=\"translate({} {}) rotate({}){}\" {}><title>piece {}</title></path>\n",
+                placement.piece_id(),
+                multi_polygon_path(&problem.piece_descriptions()[placement.piece_id()].piece, false),
+                location.x().to_f64().unwrap(),
+                location.y().to_f64().unwrap(),
+                placement.rotation().to_f64().unwrap().to_degrees(),
+                // svg applies the right most transform first, as the placement does
+                if placement.mirrored() { " scale(-1 1)" } else { "" },
+                PIECE_STYLE,
+                placement.piece_id(),
+            );
+        }
+        body += "</g>\n";
+    }
+    let view = BoundingBox {
+        min_x: bin_box.min_x,
+        min_y: bin_box.min_y,
+        max_x: bin_box.max_x + stride * (bins - 1) as f64,
+        max_y: bin_box.max_y,
+    };
+    write_document(writer, &view, &body)
+}
+
+/// Returns the bounds of all bin types on top of each other.
+fn bins_bounds<P: Polygon>(problem: &IrregularBinPackingProblem<P>) -> BoundingBox<f64> {
+    bounds(
+        problem
+            .bin_descriptions()
+            .iter()
+            .flat_map(|description| description.bin.outer().iter_vertices()),
+    )
+}
+
+/// Space left between bins and pieces laid out next to each other.
+fn gap(bounds: &BoundingBox<f64>) -> f64 {
+    bounds.width().max(bounds.height()) * 0.1
+}
+
+fn write_document<W: Write>(writer: &mut W, view: &BoundingBox<f64>, body: &str) -> Result<()> {
+    // leave room for strokes at the edges
+    let margin = gap(view) * 0.1;
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" stroke-width=\"{}\">",
+        view.min_x - margin,
+        view.min_y - margin,
+        view.width() + 2.0 * margin,
+        view.height() + 2.0 * margin,
+        margin / 2.0
+    )?;
+    write!(writer, "{}", body)?;
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+fn bounds<T: Point2D>(points: impl Iterator<Item = T>) -> BoundingBox<f64> {
+    let mut bounds = BoundingBox {
+        min_x: f64::INFINITY,
+        min_y: f64::INFINITY,
+        max_x: f64::NEG_INFINITY,
+        max_y: f64::NEG_INFINITY,
+    };
+    for point in points {
+        let (x, y) = (point.x().to_f64().unwrap(), point.y().to_f64().unwrap());
+        bounds.min_x = bounds.min_x.min(x);
+        bounds.min_y = bounds.min_y.min(y);
+        bounds.max_x = bounds.max_x.max(x);
+        bounds.max_y = bounds.max_y.max(y);
+    }
+    if bounds.min_x > bounds.max_x {
+        return BoundingBox {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 0.0,
+            max_y: 0.0,
+        };
+    }
+    bounds
+}
+
+fn contour_path<T: Point2D>(points: impl Iterator<Item = T>) -> String {
+    let mut path = String::new();
+    for (i, point) in points.enumerate() {
+        path += &format!(
+            "{}{} {} ",
+            if i == 0 { "M" } else { "L" },
+            point.x().to_f64().unwrap(),
+            point.y().to_f64().unwrap()
+        );
+    }
+    if !path.is_empty() {
+        path += "Z";
+    }
+    path
+}
+
+/// Returns the path data of the outer contour and the holes,
+/// transformed or in the local coordinates of the polygons.
+fn multi_polygon_path<P: Polygon>(multi_polygon: &MultiPolygon<P>, transformed: bool) -> String {
+    std::iter::once(multi_polygon.outer())
+        .chain(multi_polygon.holes().iter())
+        .map(|polygon| {
+            if transformed {
+                contour_path(polygon.iter_vertices())
+            } else {
+                contour_path(polygon.iter_vertices_local().copied())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{rectangle, Point2D, Polygon};
+    use crate::nesting::problem::{IrregularBinPackingPlacement, RotationPolicy};
+    use crate::parsers::svg::parse_svg;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn problem() -> IrregularBinPackingProblem<Polygon> {
+        let frame = MultiPolygon::new(
+            rectangle(4.0, 4.0).outer().clone(),
+            vec![Polygon::from_tuples([
+                (1.0, 1.0),
+                (3.0, 1.0),
+                (3.0, 3.0),
+                (1.0, 3.0),
+            ])],
+        );
+        IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(frame, vec![0.0, FRAC_PI_2], 2)
+            .piece_description(
+                MultiPolygon::new(
+                    Polygon::from_tuples([(0.0, 0.0), (3.0, 0.0), (0.0, 1.0)]),
+                    vec![],
+                ),
+                RotationPolicy::Fixed(0.0),
+                1,
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_write_solution() {
+        let problem = problem();
+        let solution = IrregularBinPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 5.0, y: 1.0 }, FRAC_PI_2),
+            IrregularBinPackingPlacement::new(0, 1, Point2D { x: 6.0, y: 6.0 }, 0.0),
+            IrregularBinPackingPlacement::new(1, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+        ]);
+        let mut svg = vec![];
+        write_solution(&mut svg, &problem, &solution).unwrap();
+        let text = String::from_utf8(svg).unwrap();
+
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let groups: Vec<_> = document
+            .descendants()
+            .filter(|node| node.has_tag_name("g"))
+            .collect();
+        assert_eq!(groups.len(), 2);
+        let pieces: Vec<_> = groups[0]
+            .descendants()
+            .filter(|node| node.attribute("class") == Some("piece"))
+            .collect();
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].attribute("data-piece-id"), Some("0"));
+        assert_eq!(pieces[0].attribute("fill-rule"), Some("evenodd"));
+
+        // reading the first bin back gives the placed pieces
+        let first_bin = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\">{}</svg>",
+            &text[groups[0].range()].replace("<path d=", "<path data-bin=\"\" d=")
+        );
+        let parsed = parse_svg::<Polygon, _>(first_bin.as_bytes(), 0.001).unwrap();
+        approx::assert_abs_diff_eq!(parsed.bin.area(), 100.0, epsilon = 1e-9);
+        assert_eq!(parsed.pieces.len(), 2);
+        let placed = solution.placements()[0].place_piece(&problem.piece_descriptions()[0].piece);
+        let frame = parsed
+            .pieces
+            .iter()
+            .find(|p| !p.holes().is_empty())
+            .unwrap();
+        approx::assert_abs_diff_eq!(frame.area(), placed.area(), epsilon = 1e-9);
+        let (a, b) = (frame.bounding_box(), placed.bounding_box());
+        approx::assert_abs_diff_eq!(a.min_x, b.min_x, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(a.max_y, b.max_y, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_write_problem_and_no_fit_polygons() {
+        let problem = problem();
+        let mut svg = vec![];
+        write_problem(&mut svg, &problem).unwrap();
+        let text = String::from_utf8(svg).unwrap();
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let pieces: Vec<_> = document
+            .descendants()
+            .filter(|node| node.attribute("class") == Some("piece"))
+            .collect();
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].attribute("data-instances"), Some("2"));
+
+        let nfp = problem.piece_descriptions()[0].piece.no_fit_polygon(
+            &problem.piece_descriptions()[1].piece,
+            true,
+            false,
+        );
+        let mut svg = vec![];
+        write_no_fit_polygons(&mut svg, &nfp).unwrap();
+        let text = String::from_utf8(svg).unwrap();
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let contours = document
+            .descendants()
+            .filter(|node| node.attribute("class") == Some("nfp"))
+            .count();
+        assert_eq!(contours, nfp.len());
+        assert!(contours > 0);
+    }
+}
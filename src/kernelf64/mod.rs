@@ -5,3 +5,27 @@ pub mod segment;
 pub use point2d::Point2D;
 pub use polygon::Polygon;
 pub use segment::Segment;
+
+/// Returns an axis aligned rectangle with its lower left corner at the origin, a test fixture
+#[cfg(test)]
+pub(crate) fn rectangle(width: f64, height: f64) -> crate::multi_polygon::MultiPolygon<Polygon> {
+    rectangle_at(0.0, width, height)
+}
+
+/// Returns an axis aligned rectangle with its lower left corner at `(min_x, 0)`, a test fixture
+#[cfg(test)]
+pub(crate) fn rectangle_at(
+    min_x: f64,
+    width: f64,
+    height: f64,
+) -> crate::multi_polygon::MultiPolygon<Polygon> {
+    crate::multi_polygon::MultiPolygon::new(
+        Polygon::from_tuples([
+            (min_x, 0.0),
+            (min_x, height),
+            (min_x + width, height),
+            (min_x + width, 0.0),
+        ]),
+        vec![],
+    )
+}
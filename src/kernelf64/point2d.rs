@@ -2,7 +2,8 @@ use approx::AbsDiffEq;
 use num_traits::Zero;
 use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point2D {
     pub x: f64,
     pub y: f64,
@@ -1,12 +1,20 @@
 use super::{Point2D, Segment};
+use crate::buffer::Buffer;
 use crate::clip::Clippable;
+use crate::minkowski::MinkowskiNoFitPolygon;
 use crate::no_fit_polygon::ComputeNoFitPolygon;
 use crate::point::Point2D as _;
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon {
     pub vertices: Vec<Point2D>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub offset: Point2D,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub rotation: f64,
+    /// reflected across the local y axis before the rotation
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mirrored: bool,
 }
 
 impl Polygon {
@@ -27,6 +35,7 @@ impl Polygon {
                 .collect(),
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         }
     }
 }
@@ -45,6 +54,7 @@ where
             vertices: vertices.into_iter().collect(),
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         }
     }
 }
@@ -85,6 +95,18 @@ impl crate::polygon::Polygon for Polygon {
         self.rotation = rotation;
     }
 
+    fn can_mirror() -> bool {
+        true
+    }
+
+    fn mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
     fn length(&self) -> usize {
         self.vertices.len()
     }
@@ -92,7 +114,12 @@ impl crate::polygon::Polygon for Polygon {
 
 impl ComputeNoFitPolygon for Polygon {
     fn get_vertex(&self, index: usize) -> <Self as crate::polygon::Polygon>::Point {
-        self.vertices[index].rotate(self.rotation) + self.offset
+        let vertex = if self.mirrored {
+            self.vertices[index].reflect()
+        } else {
+            self.vertices[index]
+        };
+        vertex.rotate(self.rotation) + self.offset
     }
 
     fn value_epsilon() -> <<<Self as crate::polygon::Polygon>::Point as crate::point::Point2D>::Value as approx::AbsDiffEq>::Epsilon{
@@ -101,3 +128,7 @@ impl ComputeNoFitPolygon for Polygon {
 }
 
 impl Clippable for Polygon {}
+
+impl MinkowskiNoFitPolygon for Polygon {}
+
+impl Buffer for Polygon {}
@@ -1,7 +1,13 @@
+pub mod boolean_ops;
 pub mod bounding_box;
+pub mod buffer;
 pub mod clip;
+pub mod curve;
+pub mod export;
 pub mod kernelf64;
+pub mod minkowski;
 pub mod multi_polygon;
+pub mod nfp_cache;
 pub mod no_fit_polygon;
 pub mod parsers;
 pub mod point;
@@ -0,0 +1,395 @@
+//! No fit polygons computed from Minkowski sums.
+//!
+//! Both polygons are decomposed into convex pieces, the Minkowski sum of every
+//! pair of convex pieces is the convex hull of the pairwise vertex sums, and the
+//! no fit polygon is the union of all of these sums.
+//! This is slower than the orbiting approach but it does not get lost on
+//! degenerate inputs such as coincident edges, so it is also used as a fallback.
+use crate::clip::{clip_contours, ClipOp, Clippable};
+use crate::no_fit_polygon::ComputeNoFitPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use num_traits::{Float, NumCast, Zero};
+
+/// Selects how no fit polygons are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NFPStrategy {
+    /// The orbiting approach of `ComputeNoFitPolygon::no_fit_polygon`.
+    Orbiting,
+    /// Unions of Minkowski sums of convex decompositions.
+    Minkowski,
+    /// Orbiting, falling back to Minkowski sums when orbiting fails
+    /// or returns an implausible result.
+    #[default]
+    OrbitingWithFallback,
+}
+
+pub trait MinkowskiNoFitPolygon: ComputeNoFitPolygon + Clippable {
+    /// Computes the no fit polygon of `other` around (or inside of) `self` from Minkowski sums.
+    /// The result uses the same reference point and coordinates as `ComputeNoFitPolygon::no_fit_polygon`.
+    /// Outside no fit polygons list the outer contours first, followed by the holes when `search_edges` is set.
+    /// Inside no fit polygons list every region `other` fits into.
+    fn minkowski_no_fit_polygon(
+        &self,
+        other: &Self,
+        inside: bool,
+        search_edges: bool,
+    ) -> Option<Vec<Vec<<Self as Polygon>::Point>>> {
+        let mut other = other.clone();
+        other.set_offset(Zero::zero());
+        let reference = other.get_vertex(0);
+        let reflected = other
+            .iter_vertices()
+            .map(|vertex| reference - vertex)
+            .collect::<Vec<_>>();
+        let other_pieces = convex_decomposition(&reflected);
+        let vertices = self.iter_vertices().collect::<Vec<_>>();
+
+        let contours = if !inside {
+            let mut union = vec![];
+            for piece in convex_decomposition(&vertices).iter() {
+                for other_piece in other_pieces.iter() {
+                    let sum = convex_minkowski_sum(piece, other_piece);
+                    union = clip_contours(&union, &[(sum, false)], ClipOp::Union);
+                }
+            }
+            union
+        } else {
+            // every position where other crosses an edge of self is forbidden
+            let mut remaining = vec![(vertices.clone(), false)];
+            for (i, start) in vertices.iter().enumerate() {
+                let end = vertices[(i + 1) % vertices.len()];
+                for other_piece in other_pieces.iter() {
+                    let swept = convex_hull(
+                        &other_piece
+                            .iter()
+                            .flat_map(|point| [*point + *start, *point + end])
+                            .collect::<Vec<_>>(),
+                    );
+                    remaining = clip_contours(&remaining, &[(swept, false)], ClipOp::Difference);
+                }
+            }
+            remaining
+        };
+
+        let mut nfp = contours
+            .iter()
+            .filter(|(contour, hole)| !hole && contour.len() >= 3)
+            .map(|(contour, _)| contour.clone())
+            .collect::<Vec<_>>();
+        if !inside && search_edges {
+            nfp.extend(
+                contours
+                    .into_iter()
+                    .filter(|(contour, hole)| *hole && contour.len() >= 3)
+                    .map(|(contour, _)| contour),
+            );
+        }
+        if nfp.is_empty() {
+            None
+        } else {
+            Some(nfp)
+        }
+    }
+
+    /// Computes the no fit polygon of `other` around (or inside of) `self` with the given strategy.
+    fn no_fit_polygon_with_strategy(
+        &self,
+        other: &Self,
+        inside: bool,
+        search_edges: bool,
+        strategy: NFPStrategy,
+    ) -> Option<Vec<Vec<<Self as Polygon>::Point>>> {
+        match strategy {
+            NFPStrategy::Orbiting => self.no_fit_polygon(other, inside, search_edges),
+            NFPStrategy::Minkowski => self.minkowski_no_fit_polygon(other, inside, search_edges),
+            NFPStrategy::OrbitingWithFallback => {
+                match self.no_fit_polygon(other, inside, search_edges) {
+                    Some(nfp) if plausible_no_fit_polygon(self, other, inside, &nfp) => Some(nfp),
+                    _ => self.minkowski_no_fit_polygon(other, inside, search_edges),
+                }
+            }
+        }
+    }
+}
+
+/// Checks the areas of a no fit polygon against the Brunn-Minkowski bounds:
+/// an outside no fit polygon covers at least `(sqrt(|a|) + sqrt(|b|))^2`
+/// and an inside no fit polygon covers at most `(sqrt(|a|) - sqrt(|b|))^2`.
+pub fn plausible_no_fit_polygon<P: Polygon + From<Vec<P::Point>>>(
+    a: &P,
+    b: &P,
+    inside: bool,
+    nfp: &[Vec<P::Point>],
+) -> bool {
+    if nfp.is_empty() || nfp.iter().any(|contour| contour.len() < 3) {
+        return false;
+    }
+    let a_root = a.area().abs().sqrt();
+    let b_root = b.area().abs().sqrt();
+    let tolerance =
+        (a_root + b_root).powi(2) * <<P::Point as Point2D>::Value as NumCast>::from(1e-6).unwrap();
+    let areas = nfp
+        .iter()
+        .map(|contour| P::from(contour.clone()).area().abs())
+        .collect::<Vec<_>>();
+    if inside {
+        areas
+            .iter()
+            .all(|area| *area <= (a_root - b_root).powi(2) + tolerance)
+    } else {
+        areas[0] + tolerance >= (a_root + b_root).powi(2)
+    }
+}
+
+/// Twice the signed area of the triangle o a b, positive when o a b turns counter-clockwise.
+fn cross<T: Point2D>(o: &T, a: &T, b: &T) -> T::Value {
+    (a.x() - o.x()) * (b.y() - o.y()) - (a.y() - o.y()) * (b.x() - o.x())
+}
+
+/// Returns the convex hull of the points in counter-clockwise order without collinear points.
+pub fn convex_hull<T: Point2D>(points: &[T]) -> Vec<T> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| {
+        a.x()
+            .partial_cmp(&b.x())
+            .unwrap()
+            .then(a.y().partial_cmp(&b.y()).unwrap())
+    });
+    points.dedup_by(|a, b| a.x() == b.x() && a.y() == b.y());
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<T> = vec![];
+    // lower chain left to right
+    for point in points.iter() {
+        while hull.len() >= 2
+            && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= T::Value::zero()
+        {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+    // upper chain right to left
+    let lower_len = hull.len() + 1;
+    for point in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len
+            && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= T::Value::zero()
+        {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+    // the upper chain ends on the first point
+    hull.pop();
+    hull
+}
+
+/// Returns the Minkowski sum of two convex polygons.
+pub fn convex_minkowski_sum<T: Point2D>(a: &[T], b: &[T]) -> Vec<T> {
+    convex_hull(
+        &a.iter()
+            .flat_map(|i| b.iter().map(move |j| *i + *j))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Splits a simple polygon into convex pieces in counter-clockwise order.
+/// The polygon is triangulated by ear clipping and neighbouring pieces are
+/// merged as long as the result stays convex (Hertel-Mehlhorn).
+pub fn convex_decomposition<T: Point2D>(contour: &[T]) -> Vec<Vec<T>> {
+    let mut vertices = contour.to_vec();
+    vertices.dedup_by(|a, b| a.x() == b.x() && a.y() == b.y());
+    while vertices.len() > 1
+        && vertices[0].x() == vertices[vertices.len() - 1].x()
+        && vertices[0].y() == vertices[vertices.len() - 1].y()
+    {
+        vertices.pop();
+    }
+    if vertices.len() < 3 {
+        return vec![];
+    }
+    let signed_area = (0..vertices.len())
+        .map(|i| {
+            cross(
+                &T::zero(),
+                &vertices[i],
+                &vertices[(i + 1) % vertices.len()],
+            )
+        })
+        .fold(T::Value::zero(), |acc, x| acc + x);
+    if signed_area < T::Value::zero() {
+        vertices.reverse();
+    }
+
+    let mut pieces = triangulate(vertices);
+
+    // merge pieces across shared diagonals while they stay convex
+    'merge: loop {
+        for i in 0..pieces.len() {
+            for j in (i + 1)..pieces.len() {
+                if let Some(merged) = merge_convex(&pieces[i], &pieces[j]) {
+                    pieces[i] = merged;
+                    pieces.swap_remove(j);
+                    continue 'merge;
+                }
+            }
+        }
+        break;
+    }
+    pieces
+}
+
+/// Ear clipping triangulation of a counter-clockwise simple polygon.
+fn triangulate<T: Point2D>(mut vertices: Vec<T>) -> Vec<Vec<T>> {
+    let mut triangles = vec![];
+    while vertices.len() > 3 {
+        let n = vertices.len();
+        let ear = (0..n).find(|&i| {
+            let (prev, current, next) = (
+                &vertices[(i + n - 1) % n],
+                &vertices[i],
+                &vertices[(i + 1) % n],
+            );
+            cross(prev, current, next) > T::Value::zero()
+                && !vertices.iter().any(|point| {
+                    !same_point(point, prev)
+                        && !same_point(point, current)
+                        && !same_point(point, next)
+                        && cross(prev, current, point) >= T::Value::zero()
+                        && cross(current, next, point) >= T::Value::zero()
+                        && cross(next, prev, point) >= T::Value::zero()
+                })
+        });
+        match ear {
+            Some(i) => {
+                triangles.push(vec![
+                    vertices[(i + n - 1) % n],
+                    vertices[i],
+                    vertices[(i + 1) % n],
+                ]);
+                vertices.remove(i);
+            }
+            None => {
+                // degenerate polygon: drop a collinear vertex if there is one, otherwise give up
+                match (0..n).find(|&i| {
+                    cross(
+                        &vertices[(i + n - 1) % n],
+                        &vertices[i],
+                        &vertices[(i + 1) % n],
+                    ) == T::Value::zero()
+                }) {
+                    Some(i) => {
+                        vertices.remove(i);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    if vertices.len() == 3 && cross(&vertices[0], &vertices[1], &vertices[2]) > T::Value::zero() {
+        triangles.push(vertices);
+    }
+    triangles
+}
+
+fn same_point<T: Point2D>(a: &T, b: &T) -> bool {
+    a.x() == b.x() && a.y() == b.y()
+}
+
+/// Merges two counter-clockwise convex polygons sharing an edge if the union is convex.
+fn merge_convex<T: Point2D>(a: &[T], b: &[T]) -> Option<Vec<T>> {
+    let (n, m) = (a.len(), b.len());
+    for k in 0..n {
+        for l in 0..m {
+            if same_point(&a[k], &b[(l + 1) % m]) && same_point(&a[(k + 1) % n], &b[l]) {
+                // walk a from the end of the shared edge back to its start, then the rest of b
+                let merged = (0..n)
+                    .map(|i| a[(k + 1 + i) % n])
+                    .chain((2..m).map(|i| b[(l + i) % m]))
+                    .collect::<Vec<_>>();
+                let len = merged.len();
+                let convex = (0..len).all(|i| {
+                    cross(&merged[i], &merged[(i + 1) % len], &merged[(i + 2) % len])
+                        >= T::Value::zero()
+                });
+                return if convex { Some(merged) } else { None };
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{Point2D, Polygon};
+    use crate::point::Point2D as _;
+    use crate::polygon::Polygon as _;
+
+    fn area(contour: &[Point2D]) -> f64 {
+        Polygon::from(contour.to_vec()).area().abs()
+    }
+
+    #[test]
+    fn test_convex_decomposition_covers_polygon() {
+        // an L shape
+        let l = Polygon::from_tuples([
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 3.0),
+            (0.0, 3.0),
+        ]);
+        let vertices = l.iter_vertices().collect::<Vec<_>>();
+        let pieces = convex_decomposition(&vertices);
+        assert_eq!(pieces.len(), 2);
+        let total: f64 = pieces.iter().map(|piece| area(piece)).sum();
+        approx::assert_abs_diff_eq!(total, 6.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_minkowski_matches_orbiting_for_convex_polygons() {
+        let a = Polygon::from_tuples([(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]);
+        let b = Polygon::from_tuples([(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)]);
+        let orbiting = a.no_fit_polygon(&b, false, false).unwrap();
+        let minkowski = a.minkowski_no_fit_polygon(&b, false, false).unwrap();
+        assert_eq!(minkowski.len(), 1);
+        approx::assert_abs_diff_eq!(area(&orbiting[0]), area(&minkowski[0]), epsilon = 1e-9);
+        for point in orbiting[0].iter() {
+            assert!(point
+                .in_polygon(&Polygon::from(minkowski[0].clone()))
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_minkowski_inside() {
+        let bin = Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 6.0), (0.0, 6.0)]);
+        let piece = Polygon::from_tuples([(1.0, 1.0), (3.0, 1.0), (3.0, 2.0), (1.0, 2.0)]);
+        let nfp = bin.minkowski_no_fit_polygon(&piece, true, false).unwrap();
+        assert_eq!(nfp.len(), 1);
+        // the first vertex of the piece can move in an 8 by 5 rectangle
+        approx::assert_abs_diff_eq!(area(&nfp[0]), 40.0, epsilon = 1e-9);
+        assert!(Point2D::from_xy(0.0, 0.0)
+            .in_polygon(&Polygon::from(nfp[0].clone()))
+            .is_none());
+
+        let too_large = Polygon::from_tuples([(0.0, 0.0), (11.0, 0.0), (11.0, 1.0), (0.0, 1.0)]);
+        assert!(bin
+            .minkowski_no_fit_polygon(&too_large, true, false)
+            .is_none());
+    }
+
+    #[test]
+    fn test_fallback_for_identical_squares() {
+        let square = Polygon::from_tuples([(0.0, 0.0), (0.0, 5.0), (5.0, 5.0), (5.0, 0.0)]);
+        let nfp = square
+            .no_fit_polygon_with_strategy(&square, false, false, NFPStrategy::OrbitingWithFallback)
+            .unwrap();
+        assert!(plausible_no_fit_polygon(&square, &square, false, &nfp));
+        approx::assert_abs_diff_eq!(area(&nfp[0]), 100.0, epsilon = 1e-9);
+    }
+}
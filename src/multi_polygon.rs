@@ -1,13 +1,20 @@
 use num_traits::{Float, Zero};
 
 use crate::{
-    bounding_box::BoundingBox, no_fit_polygon::ComputeNoFitPolygon, point::Point2D,
+    bounding_box::BoundingBox,
+    clip::{clip_contours, ClipOp, Clippable},
+    minkowski::{MinkowskiNoFitPolygon, NFPStrategy},
+    nfp_cache::NFPCache,
+    no_fit_polygon::{rectangle_inner_fit_polygon, ComputeNoFitPolygon},
+    point::Point2D,
     polygon::Polygon,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiPolygon<P: Polygon> {
     outer: P,
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
     holes: Vec<P>,
 }
 
@@ -38,6 +45,18 @@ impl<P: Polygon> MultiPolygon<P> {
         self.outer.bounding_box()
     }
 
+    /// Returns the transformed vertices of the outer contour and of every hole,
+    /// each flagged with whether it is a hole.
+    pub fn contours(&self) -> Vec<(Vec<P::Point>, bool)> {
+        std::iter::once((self.outer.iter_vertices().collect(), false))
+            .chain(
+                self.holes
+                    .iter()
+                    .map(|hole| (hole.iter_vertices().collect(), true)),
+            )
+            .collect()
+    }
+
     pub fn area(&self) -> <P::Point as Point2D>::Value {
         let mut area = Zero::zero();
         area += self.outer().area().abs();
@@ -59,18 +78,48 @@ impl<P: Polygon + ComputeNoFitPolygon> MultiPolygon<P> {
         include_outer: bool,
         include_holes: bool,
     ) -> Vec<Vec<P::Point>> {
+        self.no_fit_polygon_with(other, include_outer, include_holes, |a, b, inside| {
+            a.no_fit_polygon(b, inside, false).unwrap()
+        })
+    }
+
+    /// Same as `no_fit_polygon` but reuses no fit polygons stored in `cache`
+    /// and stores the ones that had to be computed.
+    pub fn no_fit_polygon_cached(
+        &self,
+        other: &Self,
+        include_outer: bool,
+        include_holes: bool,
+        cache: &mut NFPCache<P>,
+    ) -> Vec<Vec<P::Point>> {
+        self.no_fit_polygon_with(other, include_outer, include_holes, |a, b, inside| {
+            if let Some(nfp) = cache.get(a, b, inside, false) {
+                return nfp;
+            }
+            let nfp = a.no_fit_polygon(b, inside, false).unwrap();
+            cache.insert(a, b, inside, false, &nfp);
+            nfp
+        })
+    }
+
+    fn no_fit_polygon_with<F>(
+        &self,
+        other: &Self,
+        include_outer: bool,
+        include_holes: bool,
+        mut nfp: F,
+    ) -> Vec<Vec<P::Point>>
+    where
+        F: FnMut(&P, &P, bool) -> Vec<Vec<P::Point>>,
+    {
         let mut nfp_list = vec![];
 
         if include_outer {
-            nfp_list.extend(
-                self.outer()
-                    .no_fit_polygon(other.outer(), false, false)
-                    .unwrap(),
-            );
+            nfp_list.extend(nfp(self.outer(), other.outer(), false));
         }
         if include_holes {
             for hole in self.holes() {
-                nfp_list.extend(hole.no_fit_polygon(other.outer(), true, false).unwrap());
+                nfp_list.extend(nfp(hole, other.outer(), true));
             }
         }
 
@@ -78,6 +127,137 @@ impl<P: Polygon + ComputeNoFitPolygon> MultiPolygon<P> {
     }
 }
 
+impl<P: Clippable> MultiPolygon<P> {
+    /// Applies a boolean operation to self and other, holes included.
+    /// Every resulting outer contour comes with the holes it contains.
+    pub fn clip(&self, other: &Self, op: ClipOp) -> Vec<MultiPolygon<P>> {
+        nest_contours(clip_contours(&self.contours(), &other.contours(), op))
+    }
+
+    pub fn union(&self, other: &Self) -> Vec<MultiPolygon<P>> {
+        self.clip(other, ClipOp::Union)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Vec<MultiPolygon<P>> {
+        self.clip(other, ClipOp::Intersection)
+    }
+
+    pub fn difference(&self, other: &Self) -> Vec<MultiPolygon<P>> {
+        self.clip(other, ClipOp::Difference)
+    }
+
+    pub fn xor(&self, other: &Self) -> Vec<MultiPolygon<P>> {
+        self.clip(other, ClipOp::Xor)
+    }
+}
+
+impl<P: MinkowskiNoFitPolygon> MultiPolygon<P> {
+    /// Returns the regions where the first vertex of `piece` can be placed so that
+    /// `piece` lies inside the outer contour of self and outside of all of its holes.
+    /// Holes of the returned regions are positions where `piece` would overlap a hole of self.
+    pub fn inner_fit_polygon(&self, piece: &Self, strategy: NFPStrategy) -> Vec<MultiPolygon<P>> {
+        let regions = self.outer_inner_fit_polygon(piece, strategy);
+        let forbidden = self.hole_no_fit_polygons(piece, strategy);
+        if forbidden.is_empty() {
+            return regions
+                .into_iter()
+                .map(|contour| MultiPolygon::new(P::from(contour), vec![]))
+                .collect();
+        }
+
+        let regions = regions
+            .into_iter()
+            .map(|contour| (contour, false))
+            .collect::<Vec<_>>();
+        let mut union = vec![];
+        for contour in forbidden {
+            union = clip_contours(&union, &[(contour, false)], ClipOp::Union);
+        }
+        nest_contours(clip_contours(&regions, &union, ClipOp::Difference))
+    }
+
+    /// Returns the inner fit polygon of `piece` inside the outer contour of self, ignoring holes.
+    /// Axis aligned rectangles are handled exactly without computing a no fit polygon.
+    /// The result is empty if `piece` does not fit.
+    pub fn outer_inner_fit_polygon(
+        &self,
+        piece: &Self,
+        strategy: NFPStrategy,
+    ) -> Vec<Vec<P::Point>> {
+        self.outer_inner_fit_polygon_with(piece, |a, b, inside| {
+            a.no_fit_polygon_with_strategy(b, inside, false, strategy)
+        })
+    }
+
+    /// Returns the positions of the first vertex of `piece` where it would overlap a hole of self,
+    /// one no fit polygon per hole.
+    pub fn hole_no_fit_polygons(&self, piece: &Self, strategy: NFPStrategy) -> Vec<Vec<P::Point>> {
+        self.hole_no_fit_polygons_with(piece, |a, b, inside| {
+            a.no_fit_polygon_with_strategy(b, inside, false, strategy)
+        })
+    }
+
+    /// Same as `outer_inner_fit_polygon` but computes no fit polygons with `nfp`,
+    /// eg. to reuse cached ones.
+    pub(crate) fn outer_inner_fit_polygon_with<F>(&self, piece: &Self, nfp: F) -> Vec<Vec<P::Point>>
+    where
+        F: FnOnce(&P, &P, bool) -> Option<Vec<Vec<P::Point>>>,
+    {
+        rectangle_inner_fit_polygon(self.outer(), piece.outer())
+            .or_else(|| nfp(self.outer(), piece.outer(), true))
+            .unwrap_or_default()
+    }
+
+    /// Same as `hole_no_fit_polygons` but computes no fit polygons with `nfp`,
+    /// eg. to reuse cached ones.
+    pub(crate) fn hole_no_fit_polygons_with<F>(
+        &self,
+        piece: &Self,
+        mut nfp: F,
+    ) -> Vec<Vec<P::Point>>
+    where
+        F: FnMut(&P, &P, bool) -> Option<Vec<Vec<P::Point>>>,
+    {
+        self.holes()
+            .iter()
+            .filter_map(|hole| nfp(hole, piece.outer(), false))
+            .flatten()
+            .collect()
+    }
+}
+
+/// Groups contours flagged as holes or not into multi polygons,
+/// assigning every hole to the smallest outer contour that contains it.
+pub(crate) fn nest_contours<P: Polygon + From<Vec<P::Point>>>(
+    contours: Vec<(Vec<P::Point>, bool)>,
+) -> Vec<MultiPolygon<P>> {
+    let (holes, outers): (Vec<_>, Vec<_>) = contours.into_iter().partition(|(_, hole)| *hole);
+    let mut multi_polygons = outers
+        .into_iter()
+        .map(|(contour, _)| MultiPolygon::new(P::from(contour), vec![]))
+        .collect::<Vec<_>>();
+    for (contour, _) in holes {
+        let hole = P::from(contour);
+        let parent = multi_polygons
+            .iter_mut()
+            .filter(|multi_polygon| {
+                hole.iter_vertices()
+                    .any(|vertex| vertex.in_polygon(multi_polygon.outer()) == Some(true))
+            })
+            .min_by(|a, b| {
+                a.outer()
+                    .area()
+                    .abs()
+                    .partial_cmp(&b.outer().area().abs())
+                    .unwrap()
+            });
+        if let Some(parent) = parent {
+            parent.holes.push(hole);
+        }
+    }
+    multi_polygons
+}
+
 mod tests {
 
     #[test]
@@ -252,4 +432,147 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_inner_fit_polygon_rectangle() {
+        use super::MultiPolygon;
+        use crate::kernelf64::*;
+        use crate::minkowski::NFPStrategy;
+        use crate::polygon::Polygon as _;
+
+        let mut bin = Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 8.0), (0.0, 8.0)]);
+        bin.set_offset(Point2D { x: 5.0, y: 5.0 });
+        let bin = MultiPolygon::new(bin, vec![]);
+        let piece = MultiPolygon::new(
+            Polygon::from_tuples([(1.0, 1.0), (3.0, 1.0), (2.0, 4.0)]),
+            vec![],
+        );
+
+        let ifp = bin.inner_fit_polygon(&piece, NFPStrategy::Orbiting);
+        assert_eq!(ifp.len(), 1);
+        assert!(ifp[0].holes().is_empty());
+        let bbox = ifp[0].bounding_box();
+        assert_eq!((bbox.min_x, bbox.min_y), (5.0, 5.0));
+        assert_eq!((bbox.max_x, bbox.max_y), (13.0, 10.0));
+
+        let too_large = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (11.0, 0.0), (11.0, 1.0), (0.0, 1.0)]),
+            vec![],
+        );
+        assert!(bin
+            .inner_fit_polygon(&too_large, NFPStrategy::Orbiting)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_inner_fit_polygon_subtracts_holes() {
+        use super::MultiPolygon;
+        use crate::kernelf64::*;
+        use crate::minkowski::NFPStrategy;
+        use crate::point::Point2D as _;
+
+        let bin = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![Polygon::from_tuples([
+                (4.0, 4.0),
+                (6.0, 4.0),
+                (6.0, 6.0),
+                (4.0, 6.0),
+            ])],
+        );
+        let piece = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+            vec![],
+        );
+
+        let ifp = bin.inner_fit_polygon(&piece, NFPStrategy::OrbitingWithFallback);
+        assert_eq!(ifp.len(), 1);
+        assert_eq!(ifp[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(ifp[0].area(), 81.0 - 9.0, epsilon = 1e-9);
+        assert_eq!(
+            Point2D::from_xy(4.5, 4.5).in_polygon(&ifp[0].holes()[0]),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_boolean_operations_keep_holes() {
+        use super::MultiPolygon;
+        use crate::kernelf64::*;
+        use crate::point::Point2D as _;
+
+        // a 10 by 10 square with a 4 by 4 hole
+        let frame = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![Polygon::from_tuples([
+                (3.0, 3.0),
+                (7.0, 3.0),
+                (7.0, 7.0),
+                (3.0, 7.0),
+            ])],
+        );
+        let bar = MultiPolygon::new(
+            Polygon::from_tuples([(8.0, 4.0), (14.0, 4.0), (14.0, 6.0), (8.0, 6.0)]),
+            vec![],
+        );
+        let island = MultiPolygon::new(
+            Polygon::from_tuples([(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)]),
+            vec![],
+        );
+
+        let union = frame.union(&bar);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(union[0].area(), 84.0 + 8.0, epsilon = 1e-9);
+
+        let difference = frame.difference(&bar);
+        assert_eq!(difference.len(), 1);
+        assert_eq!(difference[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(difference[0].area(), 84.0 - 4.0, epsilon = 1e-9);
+
+        let intersection = frame.intersection(&bar);
+        assert_eq!(intersection.len(), 1);
+        approx::assert_abs_diff_eq!(intersection[0].area(), 4.0, epsilon = 1e-9);
+
+        // the island sits inside the hole, so the union is two pieces
+        let union = frame.union(&island);
+        assert_eq!(union.len(), 2);
+        let (with_hole, without_hole): (Vec<_>, Vec<_>) =
+            union.iter().partition(|p| !p.holes().is_empty());
+        assert_eq!((with_hole.len(), without_hole.len()), (1, 1));
+        assert_eq!(
+            Point2D::from_xy(5.0, 5.0).in_polygon(without_hole[0].outer()),
+            Some(true)
+        );
+
+        let xor = frame.xor(&bar);
+        let area: f64 = xor.iter().map(|p| p.area()).sum();
+        approx::assert_abs_diff_eq!(area, 84.0 + 8.0 - 4.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_no_fit_polygon_cached() {
+        use super::MultiPolygon;
+        use crate::kernelf64::*;
+        use crate::nfp_cache::NFPCache;
+        use crate::polygon::Polygon as _;
+
+        let mut outer = Polygon::from_tuples([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        outer.set_offset(Point2D { x: 5.0, y: 5.0 });
+        let mut hole = Polygon::from_tuples([(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)]);
+        hole.set_offset(Point2D { x: 5.0, y: 5.0 });
+        let piece_0 = MultiPolygon::new(outer, vec![hole]);
+        let piece_1 = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]),
+            vec![],
+        );
+
+        let mut cache = NFPCache::new();
+        let expected = piece_0.no_fit_polygon(&piece_1, true, true);
+        let computed = piece_0.no_fit_polygon_cached(&piece_1, true, true, &mut cache);
+        assert_eq!(cache.len(), 2);
+        let cached = piece_0.no_fit_polygon_cached(&piece_1, true, true, &mut cache);
+        assert_eq!(computed, expected);
+        assert_eq!(cached, expected);
+    }
 }
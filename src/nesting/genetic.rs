@@ -1,56 +1,148 @@
-//! Genetic algorithm for irregular bin packing
+//! Genetic algorithm for irregular bin packing and strip packing
 use num_traits::ToPrimitive;
 use std::collections::HashMap;
+use std::f64::consts::TAU;
 use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use super::problem::strip_packing::used_length;
 use super::problem::{
     IrregularBinPackingPlacement, IrregularBinPackingProblem, IrregularBinPackingSolution,
+    RotationPolicy, StripPackingProblem, StripPackingSolution,
 };
-use crate::no_fit_polygon::ComputeNoFitPolygon;
+use crate::buffer::JoinType;
+use crate::minkowski::{MinkowskiNoFitPolygon, NFPStrategy};
+use crate::multi_polygon::MultiPolygon;
+use crate::nfp_cache::NFPCache;
+use crate::segment::{Segment, SegmentSegmentIntersection};
 use crate::{point::Point2D, polygon::Polygon};
 use anyhow::{anyhow, Result};
-use itertools::izip;
-use num_traits::Zero;
+use approx::abs_diff_eq;
+use itertools::{izip, Itertools};
+use num_traits::{Float, NumCast, One, Zero};
 use rand::seq::SliceRandom;
 use rand::Rng;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 
-#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum NFPCacheIndex {
-    Indivudual(usize),
-    Bin,
+    Individual(usize),
+    /// a bin type
+    Bin(usize),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug)]
 struct NFPCacheKey<P: Polygon> {
     a: NFPCacheIndex,
     b: NFPCacheIndex,
     a_rotation: <P::Point as Point2D>::Value,
     b_rotation: <P::Point as Point2D>::Value,
+    a_mirrored: bool,
+    b_mirrored: bool,
     inside: bool,
 }
 
+impl<P: Polygon> Clone for NFPCacheKey<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Polygon> Copy for NFPCacheKey<P> {}
+
+/// Rotations closer than this many radians share their no fit polygons
+const ROTATION_KEY_QUANTUM: f64 = 1e-9;
+
+/// Returns the rotation in multiples of the key quantum, so rotations
+/// that only differ by rounding errors share a key
+fn quantized<T: ToPrimitive>(rotation: T) -> i64 {
+    (rotation.to_f64().unwrap() / ROTATION_KEY_QUANTUM).round() as i64
+}
+
+// rotations are compared quantized to stay consistent with the hash
+impl<P: Polygon> PartialEq for NFPCacheKey<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a
+            && self.b == other.b
+            && quantized(self.a_rotation) == quantized(other.a_rotation)
+            && quantized(self.b_rotation) == quantized(other.b_rotation)
+            && self.a_mirrored == other.a_mirrored
+            && self.b_mirrored == other.b_mirrored
+            && self.inside == other.inside
+    }
+}
+
+impl<P: Polygon> Eq for NFPCacheKey<P> {}
+
 impl<P: Polygon> Hash for NFPCacheKey<P> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.a.hash(state);
         self.b.hash(state);
-        self.a_rotation.to_f64().unwrap().to_bits().hash(state);
-        self.b_rotation.to_f64().unwrap().to_bits().hash(state);
+        quantized(self.a_rotation).hash(state);
+        quantized(self.b_rotation).hash(state);
+        self.a_mirrored.hash(state);
+        self.b_mirrored.hash(state);
         self.inside.hash(state);
     }
 }
 
-pub struct GeneticIrregularBinPacker<P: Polygon + ComputeNoFitPolygon> {
+/// No fit polygons shared between the threads evaluating individuals
+type IndexedNFPCache<P> = RwLock<HashMap<NFPCacheKey<P>, Arc<Vec<Vec<<P as Polygon>::Point>>>>>;
+
+/// How parents are chosen for crossover.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    /// Pick the fittest of n randomly chosen individuals.
+    Tournament(usize),
+    /// Pick individuals with a probability that decreases linearly with their rank.
+    Rank,
+}
+
+/// What the fitness of a solution measures
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Objective {
+    /// the number of bins used
+    Bins,
+    /// the length of the single bin used, for strip packing
+    Length,
+}
+
+pub struct GeneticIrregularBinPacker<P: MinkowskiNoFitPolygon> {
     problem: IrregularBinPackingProblem<P>,
+    objective: Objective,
+    /// pieces grown and the bins shrunk by half of the spacing, used for all no fit polygons
+    spaced_pieces: Vec<MultiPolygon<P>>,
+    spaced_bins: Vec<MultiPolygon<P>>,
+    /// bin types by ascending cost per area, the order new bins are tried in
+    bin_order: Vec<usize>,
+    /// continuous rotations are refined in multiples of this angle
+    rotation_resolution: <P::Point as Point2D>::Value,
     population_size: usize,
     mutation_rate: f64,
+    selection: Selection,
+    max_generations: Option<usize>,
+    time_limit: Option<Duration>,
+    stagnation_limit: Option<usize>,
+    threads: usize,
+    nfp_strategy: NFPStrategy,
     population: Vec<Individual<P>>,
     rng: ChaCha8Rng,
-    nfp_cache: HashMap<NFPCacheKey<P>, Vec<Vec<P::Point>>>,
+    nfp_cache: IndexedNFPCache<P>,
+    stored_nfps: RwLock<NFPCache<P>>,
+    generation: usize,
+    stagnant_generations: usize,
+    best: Option<(f64, IrregularBinPackingSolution<P>)>,
 }
 
-impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPacker<P> {
+impl<P> GeneticIrregularBinPacker<P>
+where
+    P: MinkowskiNoFitPolygon + Send + Sync,
+    P::Point: Send + Sync,
+    <P::Point as Point2D>::Value: Send + Sync,
+{
     pub fn new(
         problem: IrregularBinPackingProblem<P>,
         population_size: usize,
@@ -64,31 +156,60 @@ impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPacker<P> {
         let mut indices: Vec<usize> = (0..problem.piece_descriptions().len()).collect();
 
         // heuristic: place bigger elements first
-        indices.sort_by_key(|i| {
-            problem.piece_descriptions()[*i].piece.area();
+        indices.sort_by(|a, b| {
+            let area_a = problem.piece_descriptions()[*a].piece.area();
+            let area_b = problem.piece_descriptions()[*b].piece.area();
+            area_b.partial_cmp(&area_a).unwrap()
         });
-        indices.reverse();
 
         for i in indices {
             let piece_description = &problem.piece_descriptions()[i];
             for _ in 0..piece_description.instances {
                 order.push(i);
-                if let Some(rotation) = piece_description.allowed_rotations.choose(&mut rng) {
-                    rotations.push(rotation.clone());
-                } else {
-                    rotations.push(Zero::zero());
-                }
+                let candidates = rotation_candidates(&problem, i, false);
+                rotations.push(
+                    candidates
+                        .choose(&mut rng)
+                        .copied()
+                        .unwrap_or_else(Zero::zero),
+                );
             }
         }
-        let adam = Individual::new(order, rotations);
+        let mut bin_order: Vec<usize> = (0..problem.bin_descriptions().len()).collect();
+        let cost_per_area = |bin_type: &usize| {
+            let description = &problem.bin_descriptions()[*bin_type];
+            description.cost / description.bin.area()
+        };
+        bin_order.sort_by(|a, b| cost_per_area(a).partial_cmp(&cost_per_area(b)).unwrap());
+        let mut adam = Individual::new(order, rotations);
+        adam.bin_order = bin_order.clone();
         let population = vec![adam];
+        let (spaced_pieces, spaced_bins) = spaced_geometry(&problem);
         let mut packer = Self {
             problem,
+            objective: Objective::Bins,
+            spaced_pieces,
+            spaced_bins,
+            bin_order,
+            rotation_resolution: <<P::Point as Point2D>::Value as NumCast>::from(
+                1.0f64.to_radians(),
+            )
+            .unwrap(),
             population_size,
             mutation_rate,
+            selection: Selection::Rank,
+            max_generations: Some(100),
+            time_limit: None,
+            stagnation_limit: None,
+            threads: default_threads(),
+            nfp_strategy: NFPStrategy::default(),
             population,
             rng,
-            nfp_cache: HashMap::new(),
+            nfp_cache: RwLock::new(HashMap::new()),
+            stored_nfps: RwLock::new(NFPCache::new()),
+            generation: 0,
+            stagnant_generations: 0,
+            best: None,
         };
         while packer.population.len() < packer.population_size {
             let clone = packer.mutate(&packer.population[0].clone());
@@ -99,64 +220,661 @@ impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPacker<P> {
 
     fn mutate(&mut self, individual: &Individual<P>) -> Individual<P> {
         let mut clone = individual.clone();
+        clone.fitness = None;
         for i in 0..clone.order.len() {
             let r: f64 = self.rng.gen();
             if r < 0.01 * self.mutation_rate {
                 let j = i + 1;
                 if j < clone.order.len() {
-                    let temp = clone.order[i];
-                    clone.order[i] = clone.order[j];
-                    clone.order[j] = temp;
+                    clone.order.swap(i, j);
+                    // rotations stay with their piece
+                    clone.rotations.swap(i, j);
+                    clone.mirrored.swap(i, j);
                 }
             }
 
             let r: f64 = self.rng.gen();
             if r < 0.01 * self.mutation_rate {
-                clone.rotations[i] = self.problem.piece_descriptions()[clone.order[i]]
-                    .allowed_rotations
-                    .choose(&mut self.rng)
-                    .unwrap_or(&Zero::zero())
-                    .clone();
+                clone.rotations[i] =
+                    self.mutate_rotation(clone.order[i], clone.rotations[i], clone.mirrored[i]);
+            }
+
+            let r: f64 = self.rng.gen();
+            let description = &self.problem.piece_descriptions()[clone.order[i]];
+            if r < 0.01 * self.mutation_rate && description.may_mirror() {
+                clone.mirrored[i] = !clone.mirrored[i];
+                if description.grain.is_some() {
+                    // flipping turns the grain, so the rotation has to follow
+                    clone.rotations[i] =
+                        rotation_candidates(&self.problem, clone.order[i], clone.mirrored[i])
+                            .choose(&mut self.rng)
+                            .copied()
+                            .unwrap_or_else(Zero::zero);
+                }
+            }
+        }
+        if clone.bin_order.len() > 1 {
+            let r: f64 = self.rng.gen();
+            if r < 0.01 * self.mutation_rate {
+                let i = self.rng.gen_range(0..clone.bin_order.len() - 1);
+                clone.bin_order.swap(i, i + 1);
             }
         }
         clone
     }
 
-    fn place(&self, individual: Individual<P>) -> IrregularBinPackingSolution<P> {
-        let mut bin_id = 0;
-        let mut placed: usize = 0;
-        let mut first_in_bin: bool = true;
-        let pieces_to_place = individual.order.len();
-        //let mut bins = vec![];
-        let mut bin = self.problem.bin();
-        let mut placements: Vec<IrregularBinPackingPlacement<P>> = vec![];
-        while placed < pieces_to_place {
-            for (piece_id, rotation) in izip!(individual.order.iter(), individual.rotations.iter())
+    /// Returns a new rotation for a piece, one of the candidates of its rotation policy
+    /// that keep its grain in some bin type. A continuous rotation is refined half of the
+    /// time instead, moved by up to half a step to a nearby multiple of the rotation
+    /// resolution, as long as that keeps the grain.
+    fn mutate_rotation(
+        &mut self,
+        piece_id: usize,
+        rotation: <P::Point as Point2D>::Value,
+        mirrored: bool,
+    ) -> <P::Point as Point2D>::Value {
+        let description = &self.problem.piece_descriptions()[piece_id];
+        if let RotationPolicy::Continuous(step) = description.rotations {
+            if self.rng.gen_bool(0.5) {
+                let resolution = self.rotation_resolution;
+                let two = <P::Point as Point2D>::Value::one() + One::one();
+                let max_ticks = (step / resolution / two).to_usize().unwrap_or(1).max(1);
+                let mut ticks = <<P::Point as Point2D>::Value as NumCast>::from(
+                    self.rng.gen_range(1..=max_ticks),
+                )
+                .unwrap();
+                if self.rng.gen_bool(0.5) {
+                    ticks = -ticks;
+                }
+                // snapping to the resolution keeps the number of cached nfps bounded
+                let turn = <<P::Point as Point2D>::Value as NumCast>::from(TAU).unwrap();
+                let mut refined = ((rotation / resolution).round() + ticks) * resolution % turn;
+                if refined < Zero::zero() {
+                    refined += turn;
+                }
+                if self
+                    .problem
+                    .bin_descriptions()
+                    .iter()
+                    .any(|bin| description.keeps_grain(refined, mirrored, bin.grain_direction()))
+                {
+                    return refined;
+                }
+            }
+        }
+        rotation_candidates(&self.problem, piece_id, mirrored)
+            .choose(&mut self.rng)
+            .copied()
+            .unwrap_or_else(Zero::zero)
+    }
+
+    /// Order preserving single point crossover.
+    /// Each child keeps the genes of one parent up to a random cut point
+    /// and takes the remaining pieces in the order they appear in the other parent.
+    /// The order bin types are tried in comes from the first parent.
+    fn crossover(
+        &mut self,
+        male: &Individual<P>,
+        female: &Individual<P>,
+    ) -> (Individual<P>, Individual<P>) {
+        let cut = self.rng.gen_range(0..=male.order.len());
+        (
+            Self::splice(male, female, cut),
+            Self::splice(female, male, cut),
+        )
+    }
+
+    fn splice(head: &Individual<P>, tail: &Individual<P>, cut: usize) -> Individual<P> {
+        let mut order = head.order[..cut].to_vec();
+        let mut rotations = head.rotations[..cut].to_vec();
+        let mut mirrored = head.mirrored[..cut].to_vec();
+
+        // pieces can have several instances so count what is still missing
+        let mut missing: HashMap<usize, usize> = HashMap::new();
+        for piece_id in head.order[cut..].iter() {
+            *missing.entry(*piece_id).or_default() += 1;
+        }
+        for (piece_id, rotation, is_mirrored) in izip!(
+            tail.order.iter(),
+            tail.rotations.iter(),
+            tail.mirrored.iter()
+        ) {
+            if let Some(count) = missing.get_mut(piece_id) {
+                if *count > 0 {
+                    *count -= 1;
+                    order.push(*piece_id);
+                    rotations.push(*rotation);
+                    mirrored.push(*is_mirrored);
+                }
+            }
+        }
+        let mut child = Individual::new(order, rotations);
+        child.mirrored = mirrored;
+        child.bin_order = head.bin_order.clone();
+        child
+    }
+
+    /// Returns the index of an individual of the sorted population to use as a parent.
+    fn select(&mut self) -> usize {
+        let n = self.population.len();
+        match self.selection {
+            Selection::Tournament(size) => (0..size.max(1))
+                .map(|_| self.rng.gen_range(0..n))
+                .min()
+                .unwrap(),
+            Selection::Rank => {
+                // weights n, n - 1, ..., 1
+                let total = n * (n + 1) / 2;
+                let mut r = self.rng.gen_range(0..total);
+                for i in 0..n {
+                    let weight = n - i;
+                    if r < weight {
+                        return i;
+                    }
+                    r -= weight;
+                }
+                n - 1
+            }
+        }
+    }
+
+    /// Returns the fitness of a solution, lower is better.
+    /// The main part is the total cost of the bins used, with every piece that could
+    /// not be placed costing as many of the most expensive bins as there are pieces.
+    /// With bins that cost one it is the number of bins used.
+    /// A fraction of the smallest difference between bin costs rewards solutions
+    /// whose bins are unevenly filled, which makes it easier to empty a bin in later generations.
+    /// When strip packing the fitness is the used length instead,
+    /// every piece that could not be placed adds the length of the whole bin.
+    pub fn fitness(&self, solution: &IrregularBinPackingSolution<P>) -> f64 {
+        let n_pieces: usize = self
+            .problem
+            .piece_descriptions()
+            .iter()
+            .map(|description| description.instances)
+            .sum();
+        let unplaced = n_pieces - solution.placements().len();
+
+        if self.objective == Objective::Length {
+            let bin = self.problem.bin().bounding_box();
+            let length = used_length(self.problem.piece_descriptions(), solution.placements());
+            return unplaced as f64 * (bin.max_x - bin.min_x).to_f64().unwrap()
+                + length.to_f64().unwrap();
+        }
+
+        let utilization = solution.bin_utilization(&self.problem);
+        let mean_squared_utilization = if utilization.is_empty() {
+            0.0
+        } else {
+            utilization
+                .iter()
+                .map(|u| u.to_f64().unwrap().powi(2))
+                .sum::<f64>()
+                / utilization.len() as f64
+        };
+
+        let costs: Vec<f64> = self
+            .problem
+            .bin_descriptions()
+            .iter()
+            .map(|description| description.cost.to_f64().unwrap())
+            .collect();
+        // the smallest amount by which the costs of two solutions can differ, roughly
+        let resolution = costs
+            .iter()
+            .chain(
+                costs
+                    .iter()
+                    .tuple_combinations()
+                    .map(|(a, b)| (a - b).abs())
+                    .collect::<Vec<_>>()
+                    .iter(),
+            )
+            .copied()
+            .filter(|cost| *cost > 0.0)
+            .reduce(f64::min)
+            .unwrap_or(1.0);
+        let max_cost = costs.iter().copied().fold(resolution, f64::max);
+
+        (unplaced * n_pieces) as f64 * max_cost
+            + solution.total_cost(&self.problem).to_f64().unwrap()
+            + resolution * (1.0 - mean_squared_utilization.min(1.0))
+    }
+
+    /// Evaluates the current population and replaces it with the next generation.
+    /// Individuals are evaluated concurrently, results do not depend on the number of threads.
+    pub fn step(&mut self) {
+        // evaluate
+        let mut population = std::mem::take(&mut self.population);
+        let unevaluated: Vec<&Individual<P>> = population
+            .iter()
+            .filter(|individual| individual.fitness.is_none())
+            .collect();
+        let evaluated = parallel_map(&unevaluated, self.threads, |individual| {
+            let solution = self.place(individual);
+            (self.fitness(&solution), solution)
+        });
+
+        let mut improved = false;
+        for (individual, (fitness, solution)) in population
+            .iter_mut()
+            .filter(|individual| individual.fitness.is_none())
+            .zip(evaluated)
+        {
+            individual.fitness = Some(fitness);
+            if self.best.as_ref().is_none_or(|(best, _)| fitness < *best) {
+                self.best = Some((fitness, solution));
+                improved = true;
+            }
+        }
+        population.sort_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap());
+        self.population = population;
+
+        if improved {
+            self.stagnant_generations = 0;
+        } else {
+            self.stagnant_generations += 1;
+        }
+
+        // the fittest individual survives as is
+        let mut next = vec![self.population[0].clone()];
+        while next.len() < self.population_size {
+            let (male, female) = (self.select(), self.select());
+            let male = self.population[male].clone();
+            let female = self.population[female].clone();
+            let (son, daughter) = self.crossover(&male, &female);
+            next.push(self.mutate(&son));
+            if next.len() < self.population_size {
+                next.push(self.mutate(&daughter));
+            }
+        }
+        self.population = next;
+        self.generation += 1;
+    }
+
+    /// Runs generations until the generation limit, the time limit or the
+    /// stagnation limit is hit and returns the best solution seen so far.
+    pub fn run(&mut self) -> IrregularBinPackingSolution<P> {
+        let start = Instant::now();
+        self.precompute_nfps();
+        loop {
+            self.step();
+            if self
+                .max_generations
+                .is_some_and(|max| self.generation >= max)
+                || self
+                    .time_limit
+                    .is_some_and(|limit| start.elapsed() >= limit)
+                || self
+                    .stagnation_limit
+                    .is_some_and(|limit| self.stagnant_generations >= limit)
             {
-                // grab the piece and rotate it
-                let piece_description = &self.problem.piece_descriptions()[*piece_id];
-                let mut piece = piece_description.piece.clone();
-                piece.for_each_polygon(|p| p.set_rotation(*rotation));
-
-                // for the first placement, put the piece on the left
-                if first_in_bin {
-                    let nf = bin.no_fit_polygon(&piece, false, true);
-                    let left_most = nf
+                break;
+            }
+        }
+        self.best().unwrap().clone()
+    }
+
+    /// Get the best solution seen so far
+    pub fn best(&self) -> Option<&IrregularBinPackingSolution<P>> {
+        self.best.as_ref().map(|(_, solution)| solution)
+    }
+
+    /// Get the fitness of the best solution seen so far
+    pub fn best_fitness(&self) -> Option<f64> {
+        self.best.as_ref().map(|(fitness, _)| *fitness)
+    }
+
+    /// Get the number of generations that have been run
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Computes the no fit polygons of every pair of pieces, of every piece inside of
+    /// the holes of every other piece and inside of every bin type, for the candidate rotations
+    /// of the pieces, mirrored and not, spread over the worker threads. Refined continuous rotations are
+    /// computed when they are first used.
+    pub fn precompute_nfps(&self) {
+        // every rotation of a piece, mirrored too if it may be flipped
+        let poses: Vec<Vec<(<P::Point as Point2D>::Value, bool)>> = self
+            .problem
+            .piece_descriptions()
+            .iter()
+            .enumerate()
+            .map(|(piece_id, description)| {
+                let mirrored: &[bool] = if description.may_mirror() {
+                    &[false, true]
+                } else {
+                    &[false]
+                };
+                mirrored
+                    .iter()
+                    .flat_map(|mirrored| {
+                        rotation_candidates(&self.problem, piece_id, *mirrored)
+                            .into_iter()
+                            .map(|rotation| (rotation, *mirrored))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut keys = vec![];
+        for (b, b_poses) in poses.iter().enumerate() {
+            for (b_rotation, b_mirrored) in b_poses.iter() {
+                for (bin_type, inside) in
+                    (0..self.spaced_bins.len()).cartesian_product([true, false])
+                {
+                    keys.push(NFPCacheKey {
+                        a: NFPCacheIndex::Bin(bin_type),
+                        b: NFPCacheIndex::Individual(b),
+                        a_rotation: Zero::zero(),
+                        b_rotation: *b_rotation,
+                        a_mirrored: false,
+                        b_mirrored: *b_mirrored,
+                        inside,
+                    });
+                }
+                for (a, a_poses) in poses.iter().enumerate() {
+                    let has_holes = !self.spaced_pieces[a].holes().is_empty();
+                    for (a_rotation, a_mirrored) in a_poses.iter() {
+                        for inside in [false, true] {
+                            if inside && !has_holes {
+                                continue;
+                            }
+                            keys.push(NFPCacheKey {
+                                a: NFPCacheIndex::Individual(a),
+                                b: NFPCacheIndex::Individual(b),
+                                a_rotation: *a_rotation,
+                                b_rotation: *b_rotation,
+                                a_mirrored: *a_mirrored,
+                                b_mirrored: *b_mirrored,
+                                inside,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        let cache = self.nfp_cache.read().unwrap();
+        keys.retain(|key| !cache.contains_key(key));
+        drop(cache);
+
+        parallel_map(&keys, self.threads, |key| self.nfp(*key));
+    }
+
+    /// Returns the no fit polygon of `b` around `a` (or inside of a bin or the holes of a piece)
+    /// with `a` placed at the origin, computing it only if it is not already cached.
+    fn nfp(&self, key: NFPCacheKey<P>) -> Arc<Vec<Vec<P::Point>>> {
+        if let Some(nfp) = self.nfp_cache.read().unwrap().get(&key) {
+            return nfp.clone();
+        }
+
+        let b = self.piece_at_origin(key.b, key.b_rotation, key.b_mirrored);
+        let nfp = match key.a {
+            // the spacing left no room in the bin
+            NFPCacheIndex::Bin(i) if self.spaced_bins[i].outer().length() < 3 => None,
+            NFPCacheIndex::Bin(i) if key.inside => Some(
+                self.spaced_bins[i]
+                    .outer_inner_fit_polygon_with(&b, |a, b, inside| self.stored_nfp(a, b, inside)),
+            ),
+            // outside of the bin means inside of one of its holes
+            NFPCacheIndex::Bin(i) => Some(
+                self.spaced_bins[i]
+                    .hole_no_fit_polygons_with(&b, |a, b, inside| self.stored_nfp(a, b, inside)),
+            ),
+            // inside of a piece means inside of one of its holes
+            NFPCacheIndex::Individual(_) if key.inside => Some(
+                self.piece_at_origin(key.a, key.a_rotation, key.a_mirrored)
+                    .holes()
+                    .iter()
+                    .flat_map(|hole| {
+                        MultiPolygon::new(hole.clone(), vec![])
+                            .outer_inner_fit_polygon_with(&b, |a, b, inside| {
+                                self.stored_nfp(a, b, inside)
+                            })
+                    })
+                    .collect(),
+            ),
+            NFPCacheIndex::Individual(_) => self.stored_nfp(
+                self.piece_at_origin(key.a, key.a_rotation, key.a_mirrored)
+                    .outer(),
+                b.outer(),
+                false,
+            ),
+        }
+        .unwrap_or_default();
+
+        // another thread may have computed the same nfp in the meantime,
+        // both results are identical so keep whichever came first
+        self.nfp_cache
+            .write()
+            .unwrap()
+            .entry(key)
+            .or_insert(Arc::new(nfp))
+            .clone()
+    }
+
+    /// Returns the no fit polygon of `b` around (or inside of) `a` from the
+    /// geometry keyed cache, computing and storing it if it is missing.
+    fn stored_nfp(&self, a: &P, b: &P, inside: bool) -> Option<Vec<Vec<P::Point>>> {
+        if let Some(nfp) = self.stored_nfps.read().unwrap().get(a, b, inside, false) {
+            return Some(nfp);
+        }
+        let nfp = a.no_fit_polygon_with_strategy(b, inside, false, self.nfp_strategy)?;
+        self.stored_nfps
+            .write()
+            .unwrap()
+            .insert(a, b, inside, false, &nfp);
+        Some(nfp)
+    }
+
+    /// Returns a copy of every no fit polygon computed or loaded so far,
+    /// keyed by geometry so it can be saved and reused by other runs.
+    pub fn nfp_cache(&self) -> NFPCache<P> {
+        self.stored_nfps.read().unwrap().clone()
+    }
+
+    /// Returns a copy of the spaced piece (or bin) with no offset and the given rotation.
+    /// A mirrored piece gets reflected local vertices, the same as a piece placed with
+    /// `set_mirrored`, so the winding can be fixed before the no fit polygons are computed.
+    fn piece_at_origin(
+        &self,
+        index: NFPCacheIndex,
+        rotation: <P::Point as Point2D>::Value,
+        mirrored: bool,
+    ) -> MultiPolygon<P> {
+        let mut piece = match index {
+            NFPCacheIndex::Individual(i) => self.spaced_pieces[i].clone(),
+            NFPCacheIndex::Bin(i) => self.spaced_bins[i].clone(),
+        };
+        piece.for_each_polygon(|p| {
+            if mirrored {
+                *p = P::from(p.iter_vertices_local().map(|v| v.reflect()).collect());
+            }
+            // orbiting expects counter-clockwise polygons
+            if p.area() > Zero::zero() {
+                let mut vertices: Vec<P::Point> = p.iter_vertices_local().copied().collect();
+                vertices[1..].reverse();
+                *p = P::from(vertices);
+            }
+            p.set_offset(Zero::zero());
+            p.set_rotation(rotation);
+        });
+        piece
+    }
+
+    /// Places the pieces of an individual in order using bottom left fill.
+    /// Each piece is put into the first open bin it fits in at the left most
+    /// (then bottom most) position of the bin's feasible region. The feasible
+    /// region is the inner no fit polygon of the bin minus the no fit polygons
+    /// of the pieces already placed in that bin. The holes of placed pieces are tried
+    /// first, with the inner no fit polygons of the holes as feasible regions,
+    /// and the placement records the piece it is nested in.
+    /// A new bin is opened when a piece does not fit in any open bin, of the first
+    /// bin type in the individual's order that is still available and fits the piece.
+    /// Pieces that do not fit in an empty bin are left out of the solution.
+    pub fn place(&self, individual: &Individual<P>) -> IrregularBinPackingSolution<P> {
+        let bin_order = if individual.bin_order.len() == self.bin_order.len() {
+            &individual.bin_order
+        } else {
+            &self.bin_order
+        };
+        // placements grouped by bin
+        let mut bins: Vec<Vec<IrregularBinPackingPlacement<P>>> = vec![];
+        let mut bin_types: Vec<usize> = vec![];
+        let mut opened = vec![0; self.bin_order.len()];
+
+        for (piece_id, rotation, mirrored) in izip!(
+            individual.order.iter(),
+            individual.rotations.iter(),
+            individual.mirrored.iter()
+        ) {
+            // the inner no fit polygon of a bin type, or the positions
+            // where the piece would overlap a hole of it
+            let bin_nfp = |bin_type: usize, inside: bool| {
+                self.nfp(NFPCacheKey {
+                    a: NFPCacheIndex::Bin(bin_type),
+                    b: NFPCacheIndex::Individual(*piece_id),
+                    a_rotation: Zero::zero(),
+                    b_rotation: *rotation,
+                    a_mirrored: false,
+                    b_mirrored: *mirrored,
+                    inside,
+                })
+            };
+
+            // bin types the piece can be placed in without going against its grain
+            let description = &self.problem.piece_descriptions()[*piece_id];
+            let keeps_grain = |bin_type: usize| {
+                description.keeps_grain(
+                    *rotation,
+                    *mirrored,
+                    self.problem.bin_descriptions()[bin_type].grain_direction(),
+                )
+            };
+
+            // the nfp tracks the first vertex of the piece,
+            // the placement location is the offset of the piece
+            let reference = self
+                .piece_at_origin(NFPCacheIndex::Individual(*piece_id), *rotation, *mirrored)
+                .outer()
+                .get_vertex(0);
+
+            let mut location = None;
+            'bins: for (bin_id, bin) in bins.iter().enumerate() {
+                if !keeps_grain(bin_types[bin_id]) {
+                    continue;
+                }
+                let inner = bin_nfp(bin_types[bin_id], true);
+                if inner.is_empty() {
+                    // doesn't fit in this type of bin
+                    continue;
+                }
+                // the positions where the piece would overlap each placed piece
+                let mut placed_nfps = vec![];
+                for placed in bin.iter() {
+                    let nfp = self.nfp(NFPCacheKey {
+                        a: NFPCacheIndex::Individual(placed.piece_id()),
+                        b: NFPCacheIndex::Individual(*piece_id),
+                        a_rotation: placed.rotation(),
+                        b_rotation: *rotation,
+                        a_mirrored: placed.mirrored(),
+                        b_mirrored: *mirrored,
+                        inside: false,
+                    });
+                    if nfp.is_empty() {
+                        // we can't tell where the piece would overlap, so don't use this bin
+                        continue 'bins;
+                    }
+                    placed_nfps.push(translated::<P>(&nfp, placed.location()));
+                }
+
+                // holes of the placed pieces are filled before the free area of the bin
+                for (parent, placed) in bin.iter().enumerate() {
+                    let holes = self.nfp(NFPCacheKey {
+                        a: NFPCacheIndex::Individual(placed.piece_id()),
+                        b: NFPCacheIndex::Individual(*piece_id),
+                        a_rotation: placed.rotation(),
+                        b_rotation: *rotation,
+                        a_mirrored: placed.mirrored(),
+                        b_mirrored: *mirrored,
+                        inside: true,
+                    });
+                    if holes.is_empty() {
+                        continue;
+                    }
+                    let outer: Vec<_> = placed_nfps
+                        .iter()
+                        .enumerate()
+                        .filter(|(other, _)| *other != parent)
+                        .flat_map(|(_, nfp)| nfp.iter().cloned())
+                        .collect();
+                    if let Some(point) =
+                        bottom_left_point::<P>(&translated::<P>(&holes, placed.location()), &outer)
+                    {
+                        location = Some((bin_id, Some(parent), point));
+                        break 'bins;
+                    }
+                }
+
+                let mut outer = bin_nfp(bin_types[bin_id], false).to_vec();
+                outer.extend(placed_nfps.into_iter().flatten());
+                if let Some(point) = bottom_left_point::<P>(&inner, &outer) {
+                    location = Some((bin_id, None, point));
+                    break;
+                }
+            }
+
+            let (bin_id, parent, point) = match location {
+                Some(location) => location,
+                None => {
+                    // open a new bin
+                    let Some((bin_type, point)) = bin_order
                         .iter()
-                        .flatten()
-                        .min_by(|a, b| a.x().partial_cmp(&b.x()).unwrap())
-                        .unwrap();
-                    // TODO: add test for when polygon doesn't fit.
-                    placements.push(IrregularBinPackingPlacement::new(
-                        bin_id,
-                        *piece_id,
-                        left_most.clone(),
-                        rotation.clone(),
-                    ));
+                        .filter(|bin_type| {
+                            self.problem.bin_descriptions()[**bin_type]
+                                .quantity
+                                .is_none_or(|quantity| opened[**bin_type] < quantity)
+                                && keeps_grain(**bin_type)
+                        })
+                        .find_map(|bin_type| {
+                            bottom_left_point::<P>(
+                                &bin_nfp(*bin_type, true),
+                                &bin_nfp(*bin_type, false),
+                            )
+                            .map(|point| (*bin_type, point))
+                        })
+                    else {
+                        continue;
+                    };
+                    bins.push(vec![]);
+                    bin_types.push(bin_type);
+                    opened[bin_type] += 1;
+                    (bins.len() - 1, None, point)
                 }
+            };
+
+            let mut placement =
+                IrregularBinPackingPlacement::new(bin_id, *piece_id, point - reference, *rotation);
+            placement.set_parent(parent);
+            placement.set_mirrored(*mirrored);
+            bins[bin_id].push(placement);
+        }
+
+        // parents are indexed within their bin so far, shift them to the flattened placements
+        let mut first = 0;
+        for bin in bins.iter_mut() {
+            for placement in bin.iter_mut() {
+                placement.set_parent(placement.parent().map(|parent| first + parent));
             }
+            first += bin.len();
         }
-        unimplemented!()
+        IrregularBinPackingSolution::with_bin_types(bins.into_iter().flatten().collect(), bin_types)
+    }
+
+    /// Get the current population
+    pub fn population(&self) -> &[Individual<P>] {
+        &self.population
     }
 
     pub fn builder() -> GeneticIrregularBinPackerBuilder<P> {
@@ -164,33 +882,282 @@ impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPacker<P> {
     }
 }
 
-/// Represents order and rotation for each polygon
+/// Represents order, rotation and mirroring for each polygon
+/// and the order new bins are tried in
 #[derive(Debug, Clone)]
-struct Individual<P: Polygon> {
+pub struct Individual<P: Polygon> {
     order: Vec<usize>,
     rotations: Vec<<P::Point as Point2D>::Value>,
+    mirrored: Vec<bool>,
+    /// bin types, the packer's default order if empty
+    bin_order: Vec<usize>,
+    fitness: Option<f64>,
 }
 
 impl<P: Polygon> Individual<P> {
+    /// Creates an individual with no piece mirrored
     pub fn new(order: Vec<usize>, rotations: Vec<<P::Point as Point2D>::Value>) -> Self {
-        Self { order, rotations }
+        Self {
+            mirrored: vec![false; order.len()],
+            order,
+            rotations,
+            bin_order: vec![],
+            fitness: None,
+        }
+    }
+
+    /// Get the fitness of the individual if it has been evaluated
+    pub fn fitness(&self) -> Option<f64> {
+        self.fitness
+    }
+}
+
+/// Returns the rotations to start searching from for a piece, mirrored or not,
+/// the rotations that keep its grain in any of the bin types without duplicates.
+fn rotation_candidates<P: Polygon>(
+    problem: &IrregularBinPackingProblem<P>,
+    piece_id: usize,
+    mirrored: bool,
+) -> Vec<<P::Point as Point2D>::Value> {
+    let description = &problem.piece_descriptions()[piece_id];
+    let mut candidates = vec![];
+    for bin in problem.bin_descriptions().iter() {
+        for rotation in description.rotation_candidates(bin.grain_direction(), mirrored) {
+            if !candidates
+                .iter()
+                .any(|candidate| quantized(*candidate) == quantized(rotation))
+            {
+                candidates.push(rotation);
+            }
+        }
     }
+    candidates
 }
 
-pub struct GeneticIrregularBinPackerBuilder<P: Polygon + ComputeNoFitPolygon> {
+/// Returns the number of threads to use when none is given.
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Applies `f` to every item using up to `threads` scoped threads.
+/// The results are in the same order as the items.
+fn parallel_map<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if items.is_empty() {
+        return vec![];
+    }
+    let chunk_size = items.len().div_ceil(threads.max(1));
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Returns the pieces grown and the bins shrunk by half of the problem's spacing,
+/// so pieces touching each other or the bin boundary are a full spacing apart.
+/// Pieces keep their local frame, a bin that disappears is replaced by an empty polygon.
+fn spaced_geometry<P: MinkowskiNoFitPolygon>(
+    problem: &IrregularBinPackingProblem<P>,
+) -> (Vec<MultiPolygon<P>>, Vec<MultiPolygon<P>>) {
+    let pieces = problem
+        .piece_descriptions()
+        .iter()
+        .map(|description| description.piece.clone());
+    let bins = problem
+        .bin_descriptions()
+        .iter()
+        .map(|description| &description.bin);
+    if problem.spacing() == Zero::zero() {
+        return (pieces.collect(), bins.cloned().collect());
+    }
+
+    let half = problem.spacing() / (<P::Point as Point2D>::Value::one() + One::one());
+    let join = JoinType::Miter(<<P::Point as Point2D>::Value as NumCast>::from(4.0).unwrap());
+    let largest = |multi_polygons: Vec<MultiPolygon<P>>| {
+        multi_polygons
+            .into_iter()
+            .max_by(|a, b| a.area().partial_cmp(&b.area()).unwrap())
+    };
+    let spaced_pieces = pieces
+        .map(|mut piece| {
+            piece.for_each_polygon(|p| {
+                p.set_offset(Zero::zero());
+                p.set_rotation(Zero::zero());
+            });
+            largest(piece.buffer(half, join)).unwrap_or(piece)
+        })
+        .collect();
+    let spaced_bins = bins
+        .map(|bin| {
+            largest(bin.buffer(-half, join))
+                .unwrap_or_else(|| MultiPolygon::new(P::from(vec![]), vec![]))
+        })
+        .collect();
+    (spaced_pieces, spaced_bins)
+}
+
+/// Returns the contours moved by `offset`
+fn translated<P: Polygon>(contours: &[Vec<P::Point>], offset: P::Point) -> Vec<Vec<P::Point>> {
+    contours
+        .iter()
+        .map(|contour| contour.iter().map(|point| *point + offset).collect())
+        .collect()
+}
+
+/// Returns the left most, then bottom most, point that is inside or on one of the
+/// `inner` contours and not strictly inside any of the `outer` contours.
+/// Candidates are the vertices of all contours and the intersections of their edges.
+fn bottom_left_point<P>(inner: &[Vec<P::Point>], outer: &[Vec<P::Point>]) -> Option<P::Point>
+where
+    P: Polygon + From<Vec<P::Point>>,
+{
+    let inner_polygons: Vec<P> = inner.iter().map(|c| P::from(c.clone())).collect();
+    let outer_polygons: Vec<P> = outer.iter().map(|c| P::from(c.clone())).collect();
+
+    let feasible = |point: &P::Point| {
+        inner_polygons
+            .iter()
+            .any(|polygon| point.in_polygon(polygon) != Some(false))
+            && outer_polygons
+                .iter()
+                .all(|polygon| point.in_polygon(polygon) != Some(true))
+    };
+
+    let mut candidates: Vec<P::Point> = inner
+        .iter()
+        .chain(outer.iter())
+        .flatten()
+        .copied()
+        .collect();
+    let polygons: Vec<&P> = inner_polygons.iter().chain(outer_polygons.iter()).collect();
+    for (i, a) in polygons.iter().enumerate() {
+        for b in polygons.iter().skip(i + 1) {
+            for (sa, sb) in a.iter_segments().cartesian_product(b.iter_segments()) {
+                match sa.intersects_segment(&sb, false) {
+                    SegmentSegmentIntersection::Intersection(point)
+                    | SegmentSegmentIntersection::Touching(point) => candidates.push(point),
+                    SegmentSegmentIntersection::Overlap(start, end) => {
+                        candidates.push(start);
+                        candidates.push(end);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|point| feasible(point))
+        .min_by(|a, b| {
+            if abs_diff_eq!(a.x(), b.x(), epsilon = P::Point::value_epsilon()) {
+                a.y().partial_cmp(&b.y()).unwrap()
+            } else {
+                a.x().partial_cmp(&b.x()).unwrap()
+            }
+        })
+}
+
+/// Genetic algorithm for strip packing.
+/// Packs into a single bin, the strip cut at a length all pieces fit in,
+/// with bottom left fill pushing the pieces toward the start of the strip,
+/// and evolves the order and rotations of the pieces to minimize the used length.
+pub struct GeneticStripPacker<P: MinkowskiNoFitPolygon> {
+    problem: StripPackingProblem<P>,
+    packer: GeneticIrregularBinPacker<P>,
+}
+
+impl<P> GeneticStripPacker<P>
+where
+    P: MinkowskiNoFitPolygon + Send + Sync,
+    P::Point: Send + Sync,
+    <P::Point as Point2D>::Value: Send + Sync,
+{
+    /// Creates a packer with the options of `builder`, its problem is replaced by `problem`.
+    pub fn new(
+        problem: StripPackingProblem<P>,
+        builder: GeneticIrregularBinPackerBuilder<P>,
+    ) -> Result<Self> {
+        let mut packer = builder.problem(problem.to_bin_packing_problem()).build()?;
+        packer.objective = Objective::Length;
+        Ok(Self { problem, packer })
+    }
+
+    /// Evaluates the current population and replaces it with the next generation.
+    pub fn step(&mut self) {
+        self.packer.step();
+    }
+
+    /// Runs generations until a termination condition of the builder is hit
+    /// and returns the best solution seen so far.
+    pub fn run(&mut self) -> StripPackingSolution<P> {
+        self.packer.run();
+        self.best().unwrap()
+    }
+
+    /// Get the best solution seen so far
+    pub fn best(&self) -> Option<StripPackingSolution<P>> {
+        self.packer
+            .best()
+            .map(|solution| StripPackingSolution::new(solution.placements().clone()))
+    }
+
+    /// Get the used length of the best solution seen so far
+    pub fn best_length(&self) -> Option<<P::Point as Point2D>::Value> {
+        self.best().map(|solution| solution.length(&self.problem))
+    }
+
+    /// Get the number of generations that have been run
+    pub fn generation(&self) -> usize {
+        self.packer.generation()
+    }
+}
+
+pub struct GeneticIrregularBinPackerBuilder<P: MinkowskiNoFitPolygon> {
     problem: Option<IrregularBinPackingProblem<P>>,
     population_size: usize,
     mutation_rate: f64,
     seed: u64,
+    selection: Selection,
+    max_generations: Option<usize>,
+    time_limit: Option<Duration>,
+    stagnation_limit: Option<usize>,
+    threads: usize,
+    nfp_strategy: NFPStrategy,
+    nfp_cache: Option<NFPCache<P>>,
+    rotation_resolution: Option<<P::Point as Point2D>::Value>,
 }
 
-impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPackerBuilder<P> {
+impl<P> GeneticIrregularBinPackerBuilder<P>
+where
+    P: MinkowskiNoFitPolygon + Send + Sync,
+    P::Point: Send + Sync,
+    <P::Point as Point2D>::Value: Send + Sync,
+{
     pub fn new() -> Self {
         Self {
             problem: None,
             population_size: 10,
             mutation_rate: 10.0,
             seed: 0,
+            selection: Selection::Rank,
+            max_generations: Some(100),
+            time_limit: None,
+            stagnation_limit: None,
+            threads: default_threads(),
+            nfp_strategy: NFPStrategy::default(),
+            nfp_cache: None,
+            rotation_resolution: None,
         }
     }
 
@@ -214,12 +1181,718 @@ impl<P: Polygon + ComputeNoFitPolygon> GeneticIrregularBinPackerBuilder<P> {
         self
     }
 
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Stop after this many generations, None to run without a generation limit
+    pub fn max_generations(mut self, max_generations: Option<usize>) -> Self {
+        self.max_generations = max_generations;
+        self
+    }
+
+    /// Stop once a run has taken this long
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Stop after this many generations without an improvement of the best solution
+    pub fn stagnation_limit(mut self, stagnation_limit: usize) -> Self {
+        self.stagnation_limit = Some(stagnation_limit);
+        self
+    }
+
+    /// Number of worker threads used to compute nfps and evaluate individuals.
+    /// Defaults to the available parallelism of the machine.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// How no fit polygons are computed, orbiting with a Minkowski sum fallback by default
+    pub fn nfp_strategy(mut self, nfp_strategy: NFPStrategy) -> Self {
+        self.nfp_strategy = nfp_strategy;
+        self
+    }
+
+    /// No fit polygons from previous runs, for example loaded with `NFPCache::load_from_file`
+    pub fn nfp_cache(mut self, nfp_cache: NFPCache<P>) -> Self {
+        self.nfp_cache = Some(nfp_cache);
+        self
+    }
+
+    /// The smallest change of a continuous rotation, in radians. Defaults to one degree.
+    pub fn rotation_resolution(
+        mut self,
+        rotation_resolution: <P::Point as Point2D>::Value,
+    ) -> Self {
+        self.rotation_resolution = Some(rotation_resolution);
+        self
+    }
+
     pub fn build(self) -> Result<GeneticIrregularBinPacker<P>> {
-        Ok(GeneticIrregularBinPacker::new(
+        if self.population_size == 0 {
+            return Err(anyhow!("Population size must be at least 1"));
+        }
+        if self.max_generations.is_none()
+            && self.time_limit.is_none()
+            && self.stagnation_limit.is_none()
+        {
+            return Err(anyhow!("No termination condition provided"));
+        }
+        if self
+            .rotation_resolution
+            .is_some_and(|resolution| resolution <= Zero::zero())
+        {
+            return Err(anyhow!("Rotation resolution must be positive"));
+        }
+        let mut packer = GeneticIrregularBinPacker::new(
             self.problem.ok_or(anyhow!("No problem provided"))?,
             self.population_size,
             self.mutation_rate,
             self.seed,
-        ))
+        );
+        packer.selection = self.selection;
+        packer.max_generations = self.max_generations;
+        packer.time_limit = self.time_limit;
+        packer.stagnation_limit = self.stagnation_limit;
+        packer.threads = self.threads;
+        packer.nfp_strategy = self.nfp_strategy;
+        if let Some(nfp_cache) = self.nfp_cache {
+            packer.stored_nfps = RwLock::new(nfp_cache);
+        }
+        if let Some(rotation_resolution) = self.rotation_resolution {
+            // the initial population was mutated with the default resolution
+            packer.rotation_resolution = rotation_resolution;
+            packer.population.truncate(1);
+            while packer.population.len() < packer.population_size {
+                let clone = packer.mutate(&packer.population[0].clone());
+                packer.population.push(clone);
+            }
+        }
+        Ok(packer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{rectangle, Point2D, Polygon};
+    use crate::nesting::problem::PieceDescription;
+    use crate::nesting::validation::validate;
+    use crate::polygon::Polygon as _;
+
+    #[test]
+    fn test_place_fills_bins_in_order() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(5.0, 5.0), RotationPolicy::Fixed(0.0), 5)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .build()
+            .unwrap();
+        let individual = packer.population()[0].clone();
+        let solution = packer.place(&individual);
+
+        let placements = solution.placements();
+        assert_eq!(placements.len(), 5);
+        assert_eq!(placements.iter().filter(|p| p.bin_id() == 0).count(), 4);
+        assert_eq!(placements[4].bin_id(), 1);
+
+        let pieces: Vec<_> = placements
+            .iter()
+            .map(|p| p.place_piece(&problem.piece_descriptions()[p.piece_id()].piece))
+            .collect();
+        for (placement, piece) in placements.iter().zip(pieces.iter()) {
+            let bbox = piece.bounding_box();
+            assert!(bbox.min_x > -1e-9 && bbox.max_x < 10.0 + 1e-9);
+            assert!(bbox.min_y > -1e-9 && bbox.max_y < 10.0 + 1e-9);
+            assert_eq!(
+                placement.location(),
+                Point2D {
+                    x: bbox.min_x,
+                    y: bbox.min_y
+                }
+            );
+        }
+        for (i, j) in (0..4).tuple_combinations() {
+            assert!(!pieces[i].outer().intersects_polygon(pieces[j].outer()));
+            assert_ne!(placements[i].location(), placements[j].location());
+        }
+    }
+
+    #[test]
+    fn test_place_with_minkowski_strategy() {
+        let l = MultiPolygon::new(
+            Polygon::from_tuples([
+                (0.0, 0.0),
+                (4.0, 0.0),
+                (4.0, 1.0),
+                (1.0, 1.0),
+                (1.0, 3.0),
+                (0.0, 3.0),
+            ]),
+            vec![],
+        );
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(l, RotationPolicy::Fixed(0.0), 4)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .nfp_strategy(NFPStrategy::Minkowski)
+            .build()
+            .unwrap();
+        let solution = packer.place(&packer.population()[0].clone());
+
+        let placements = solution.placements();
+        assert_eq!(placements.len(), 4);
+        assert!(placements.iter().all(|p| p.bin_id() == 0));
+        assert!(validate(&problem, &solution, 1e-6).is_empty());
+        let pieces: Vec<_> = placements
+            .iter()
+            .map(|p| p.place_piece(&problem.piece_descriptions()[p.piece_id()].piece))
+            .collect();
+        for piece in pieces.iter() {
+            let bbox = piece.bounding_box();
+            assert!(bbox.min_x > -1e-9 && bbox.max_x < 10.0 + 1e-9);
+            assert!(bbox.min_y > -1e-9 && bbox.max_y < 10.0 + 1e-9);
+        }
+        for (i, j) in (0..4).tuple_combinations() {
+            assert!(!pieces[i].outer().intersects_polygon(pieces[j].outer()));
+        }
+    }
+
+    #[test]
+    fn test_place_avoids_bin_holes() {
+        let bin = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]),
+            vec![Polygon::from_tuples([
+                (0.0, 0.0),
+                (0.0, 4.0),
+                (4.0, 4.0),
+                (4.0, 0.0),
+            ])],
+        );
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(bin.clone())
+            .piece_description(rectangle(3.0, 3.0), RotationPolicy::Fixed(0.0), 4)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .build()
+            .unwrap();
+        let solution = packer.place(&packer.population()[0].clone());
+
+        let placements = solution.placements();
+        assert_eq!(placements.len(), 4);
+        assert!(placements.iter().all(|p| p.bin_id() == 0));
+        for placement in placements.iter() {
+            let piece =
+                placement.place_piece(&problem.piece_descriptions()[placement.piece_id()].piece);
+            let bbox = piece.bounding_box();
+            assert!(bbox.min_x >= 4.0 - 1e-9 || bbox.min_y >= 4.0 - 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_place_keeps_spacing() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(11.0, 11.0))
+            .piece_description(rectangle(4.0, 4.0), RotationPolicy::Fixed(0.0), 4)
+            .spacing(1.0)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .build()
+            .unwrap();
+        let solution = packer.place(&packer.population()[0].clone());
+
+        let placements = solution.placements();
+        assert_eq!(placements.len(), 4);
+        assert!(placements.iter().all(|p| p.bin_id() == 0));
+        let boxes: Vec<_> = placements
+            .iter()
+            .map(|placement| {
+                placement
+                    .place_piece(&problem.piece_descriptions()[placement.piece_id()].piece)
+                    .bounding_box()
+            })
+            .collect();
+        for (i, a) in boxes.iter().enumerate() {
+            assert!(a.min_x >= 1.0 - 1e-9 && a.min_y >= 1.0 - 1e-9);
+            assert!(a.max_x <= 10.0 + 1e-9 && a.max_y <= 10.0 + 1e-9);
+            for b in boxes.iter().skip(i + 1) {
+                let gap = (b.min_x - a.max_x)
+                    .max(a.min_x - b.max_x)
+                    .max(b.min_y - a.max_y)
+                    .max(a.min_y - b.max_y);
+                assert!(gap >= 1.0 - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_place_nests_pieces_in_holes() {
+        let frame = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (4.0, 0.0)]),
+            vec![Polygon::from_tuples([
+                (1.0, 1.0),
+                (1.0, 3.0),
+                (3.0, 3.0),
+                (3.0, 1.0),
+            ])],
+        );
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(4.0, 4.0))
+            .piece_description(frame, RotationPolicy::Fixed(0.0), 2)
+            .piece_description(rectangle(1.0, 1.0), RotationPolicy::Fixed(0.0), 5)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .build()
+            .unwrap();
+        let solution = packer.place(&packer.population()[0].clone());
+
+        let placements = solution.placements();
+        assert_eq!(placements.len(), 7);
+        assert_eq!(solution.bins_used(), 2);
+        for placement in placements.iter() {
+            let Some(parent) = placement.parent() else {
+                assert_eq!(placement.piece_id(), 0);
+                continue;
+            };
+            assert_eq!(placement.piece_id(), 1);
+            let parent = &placements[parent];
+            assert_eq!(parent.piece_id(), 0);
+            assert_eq!(parent.bin_id(), placement.bin_id());
+            let bbox = placement
+                .place_piece(&problem.piece_descriptions()[1].piece)
+                .bounding_box();
+            assert!(bbox.min_x >= 1.0 - 1e-9 && bbox.max_x <= 3.0 + 1e-9);
+            assert!(bbox.min_y >= 1.0 - 1e-9 && bbox.max_y <= 3.0 + 1e-9);
+        }
+        // four squares fill the hole of the first frame, the last one goes in the second
+        assert_eq!(
+            placements.iter().filter(|p| p.parent() == Some(0)).count(),
+            4
+        );
+        assert_eq!(placements[6].parent(), Some(5));
+        assert!(validate(&problem, &solution, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_place_skips_pieces_larger_than_bin() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(20.0, 5.0), RotationPolicy::Fixed(0.0), 1)
+            .piece_description(rectangle(5.0, 5.0), RotationPolicy::Fixed(0.0), 1)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(1)
+            .build()
+            .unwrap();
+        let individual = packer.population()[0].clone();
+        let solution = packer.place(&individual);
+        assert_eq!(solution.placements().len(), 1);
+        assert_eq!(solution.placements()[0].piece_id(), 1);
+        assert_eq!(solution.placements()[0].bin_id(), 0);
+    }
+
+    #[test]
+    fn test_place_mirrored_piece() {
+        // the triangles only fit with their right angles in the same corner
+        let bin = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (0.0, 5.0), (5.0, 0.0)]),
+            vec![],
+        );
+        let mut triangle = PieceDescription::new(
+            MultiPolygon::new(
+                Polygon::from_tuples([(0.0, 0.0), (4.0, 4.0), (4.0, 0.0)]),
+                vec![],
+            ),
+            RotationPolicy::Fixed(0.0),
+            1,
+        );
+        triangle.allow_mirror = true;
+        let problem = IrregularBinPackingProblem::new(bin.clone(), vec![triangle]);
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .build()
+            .unwrap();
+
+        let mut individual = Individual::new(vec![0], vec![0.0]);
+        assert!(packer.place(&individual).placements().is_empty());
+
+        individual.mirrored = vec![true];
+        let solution = packer.place(&individual);
+        assert_eq!(solution.placements().len(), 1);
+        let placement = &solution.placements()[0];
+        assert!(placement.mirrored());
+        let piece = placement.place_piece(&problem.piece_descriptions()[0].piece);
+        for vertex in piece.outer().iter_vertices() {
+            assert!(vertex.x >= -1e-9 && vertex.y >= -1e-9);
+            assert!(vertex.x + vertex.y <= 5.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_run_keeps_grain() {
+        use crate::nesting::problem::{BinDescription, Grain};
+        use std::f64::consts::FRAC_PI_2;
+
+        // the grain runs along the length of the pieces and across the bin
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_descriptions([BinDescription::new(rectangle(10.0, 10.0), None, 1.0)
+                .with_grain(Point2D { x: 0.0, y: 1.0 })])
+            .piece_descriptions([PieceDescription::new(
+                rectangle(4.0, 1.0),
+                RotationPolicy::Step(FRAC_PI_2),
+                6,
+            )
+            .with_grain(Grain::new(Point2D { x: 1.0, y: 0.0 }, 0.0, false))])
+            .build()
+            .unwrap();
+        assert_eq!(rotation_candidates(&problem, 0, false), [FRAC_PI_2]);
+
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(4)
+            .max_generations(Some(3))
+            .mutation_rate(50.0)
+            .threads(1)
+            .build()
+            .unwrap();
+        let solution = packer.run();
+        assert_eq!(solution.placements().len(), 6);
+        assert!(validate(&problem, &solution, 1e-6).is_empty());
+        for placement in solution.placements() {
+            approx::assert_abs_diff_eq!(placement.rotation(), FRAC_PI_2);
+        }
+    }
+
+    #[test]
+    fn test_mutate_rotation_follows_policy() {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        let quarters = [0.0, FRAC_PI_2, PI, 3.0 * FRAC_PI_2];
+        assert_eq!(RotationPolicy::Step(FRAC_PI_2).candidates(), quarters);
+        // problems made without the builder may hold invalid steps
+        for step in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(RotationPolicy::Continuous(step).candidates(), [0.0]);
+        }
+        assert!(IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(1.0, 1.0), RotationPolicy::Continuous(0.0), 1)
+            .build()
+            .is_err());
+        for rotations in [
+            RotationPolicy::Step(f64::NAN),
+            RotationPolicy::Discrete(vec![]),
+        ] {
+            assert!(IrregularBinPackingProblem::builder()
+                .bin(rectangle(10.0, 10.0))
+                .piece_description(rectangle(1.0, 1.0), rotations, 1)
+                .build()
+                .is_err());
+        }
+        assert!(IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(1.0, 1.0), Vec::<f64>::new(), 1)
+            .build()
+            .is_err());
+
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(1.0, 1.0), RotationPolicy::Fixed(0.5), 1)
+            .piece_description(rectangle(1.0, 1.0), RotationPolicy::Step(FRAC_PI_2), 1)
+            .piece_description(
+                rectangle(1.0, 1.0),
+                RotationPolicy::Continuous(FRAC_PI_2),
+                1,
+            )
+            .build()
+            .unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(1)
+            .rotation_resolution(0.1)
+            .build()
+            .unwrap();
+        let mut refined = 0;
+        for _ in 0..100 {
+            assert_eq!(packer.mutate_rotation(0, 0.5, false), 0.5);
+            assert!(quarters.contains(&packer.mutate_rotation(1, 0.0, false)));
+            let rotation = packer.mutate_rotation(2, 0.0, false);
+            if quarters.contains(&rotation) {
+                continue;
+            }
+            refined += 1;
+            // a multiple of the resolution, at most half a step away
+            let offset = if rotation > PI {
+                rotation - TAU
+            } else {
+                rotation
+            };
+            let ticks = offset / 0.1;
+            approx::assert_abs_diff_eq!(ticks, ticks.round(), epsilon = 1e-9);
+            assert!((1.0..=7.0).contains(&ticks.round().abs()));
+        }
+        assert!(refined > 0);
+    }
+
+    #[test]
+    fn test_crossover_keeps_every_piece() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(5.0, 5.0), RotationPolicy::Fixed(0.0), 3)
+            .piece_description(rectangle(2.0, 5.0), RotationPolicy::Fixed(0.0), 2)
+            .piece_description(rectangle(1.0, 1.0), RotationPolicy::Fixed(0.0), 1)
+            .build()
+            .unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(1)
+            .build()
+            .unwrap();
+        let male = Individual::new(vec![0, 0, 1, 2, 0, 1], vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let female = Individual::new(vec![2, 1, 1, 0, 0, 0], vec![3.0, 5.0, 2.0, 0.0, 1.0, 4.0]);
+        for _ in 0..10 {
+            let (son, daughter) = packer.crossover(&male, &female);
+            for child in [son, daughter] {
+                let mut order = child.order.clone();
+                order.sort();
+                assert_eq!(order, vec![0, 0, 0, 1, 1, 2]);
+                assert_eq!(child.rotations.len(), child.order.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_stops_on_stagnation() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(rectangle(5.0, 5.0), RotationPolicy::Fixed(0.0), 5)
+            .piece_description(
+                rectangle(2.0, 3.0),
+                vec![0.0, std::f64::consts::FRAC_PI_2],
+                2,
+            )
+            .build()
+            .unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(4)
+            .selection(Selection::Tournament(2))
+            .max_generations(None)
+            .stagnation_limit(3)
+            .build()
+            .unwrap();
+        let solution = packer.run();
+        assert!(packer.generation() >= 3);
+        assert_eq!(solution.placements().len(), 7);
+        assert_eq!(solution.bins_used(), 2);
+        assert!(packer.best_fitness().unwrap() < 3.0);
+    }
+
+    #[test]
+    fn test_build_requires_termination() {
+        let result = GeneticIrregularBinPacker::builder()
+            .problem(
+                IrregularBinPackingProblem::builder()
+                    .bin(rectangle(10.0, 10.0))
+                    .build()
+                    .unwrap(),
+            )
+            .max_generations(None)
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_run_is_deterministic_across_threads() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(
+                rectangle(5.0, 3.0),
+                vec![0.0, std::f64::consts::FRAC_PI_2],
+                4,
+            )
+            .piece_description(
+                rectangle(2.0, 3.0),
+                vec![0.0, std::f64::consts::FRAC_PI_2],
+                3,
+            )
+            .build()
+            .unwrap();
+        let solutions: Vec<_> = [1, 3]
+            .into_iter()
+            .map(|threads| {
+                let mut packer = GeneticIrregularBinPacker::builder()
+                    .problem(problem.clone())
+                    .population_size(6)
+                    .max_generations(Some(4))
+                    .seed(7)
+                    .threads(threads)
+                    .build()
+                    .unwrap();
+                packer.run()
+            })
+            .collect();
+        assert_eq!(
+            solutions[0].placements().len(),
+            solutions[1].placements().len()
+        );
+        for (a, b) in solutions[0]
+            .placements()
+            .iter()
+            .zip(solutions[1].placements().iter())
+        {
+            assert_eq!(a.bin_id(), b.bin_id());
+            assert_eq!(a.piece_id(), b.piece_id());
+            assert_eq!(a.location(), b.location());
+            assert_eq!(a.rotation(), b.rotation());
+        }
+    }
+
+    #[test]
+    fn test_nfp_cache_is_reused() {
+        let triangle = MultiPolygon::new(
+            Polygon::from_tuples([(0.0, 0.0), (4.0, 0.0), (2.0, 3.0)]),
+            vec![],
+        );
+        let problem = IrregularBinPackingProblem::builder()
+            .bin(rectangle(10.0, 10.0))
+            .piece_description(triangle, vec![0.0], 3)
+            .build()
+            .unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(2)
+            .max_generations(Some(1))
+            .build()
+            .unwrap();
+        let solution = packer.run();
+        let cache = packer.nfp_cache();
+        assert_eq!(cache.len(), 1);
+
+        let mut bytes = vec![];
+        cache.save(&mut bytes).unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(2)
+            .max_generations(Some(1))
+            .nfp_cache(NFPCache::load(bytes.as_slice()).unwrap())
+            .build()
+            .unwrap();
+        let cached_solution = packer.run();
+        assert_eq!(packer.nfp_cache().len(), 1);
+        for (a, b) in solution
+            .placements()
+            .iter()
+            .zip(cached_solution.placements().iter())
+        {
+            assert_eq!(a.location(), b.location());
+        }
+    }
+
+    #[test]
+    fn test_strip_packer_compacts_toward_start() {
+        let problem = StripPackingProblem::builder()
+            .width(2.0)
+            .piece_description(
+                rectangle(2.0, 1.0),
+                vec![0.0, std::f64::consts::FRAC_PI_2],
+                4,
+            )
+            .build()
+            .unwrap();
+        let mut packer = GeneticStripPacker::new(
+            problem.clone(),
+            GeneticIrregularBinPacker::builder()
+                .population_size(6)
+                .max_generations(Some(5))
+                .threads(1),
+        )
+        .unwrap();
+        let solution = packer.run();
+        assert_eq!(solution.placements().len(), 4);
+        assert!(solution
+            .placements()
+            .iter()
+            .all(|placement| placement.bin_id() == 0));
+        // the pieces fill a 2 by 4 rectangle at the start of the strip
+        approx::assert_abs_diff_eq!(solution.length(&problem), 4.0, epsilon = 1e-6);
+        approx::assert_abs_diff_eq!(solution.utilization(&problem), 1.0, epsilon = 1e-6);
+        assert_eq!(packer.best_length(), Some(solution.length(&problem)));
+    }
+
+    #[test]
+    fn test_place_minimizes_bin_cost() {
+        // a remnant holds two of the squares but using it means buying a sheet for the rest
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_description(rectangle(2.0, 2.0), None, 10.0)
+            .bin_description(rectangle(2.0, 1.0), Some(1), 1.0)
+            .piece_description(rectangle(1.0, 1.0), vec![0.0], 4)
+            .build()
+            .unwrap();
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(1)
+            .max_generations(Some(1))
+            .build()
+            .unwrap();
+        // the remnant is the cheapest per area so it is tried first
+        let greedy = packer.run();
+        assert_eq!(greedy.bins_used(), 2);
+        assert_eq!((greedy.bin_type(0), greedy.bin_type(1)), (1, 0));
+        assert_eq!(greedy.total_cost(&problem), 11.0);
+
+        let mut packer = GeneticIrregularBinPacker::builder()
+            .problem(problem.clone())
+            .population_size(10)
+            .max_generations(Some(10))
+            .mutation_rate(50.0)
+            .threads(1)
+            .build()
+            .unwrap();
+        let solution = packer.run();
+        assert_eq!(solution.placements().len(), 4);
+        assert_eq!(solution.bins_used(), 1);
+        assert_eq!(solution.bin_type(0), 0);
+        assert_eq!(solution.total_cost(&problem), 10.0);
+        assert!(packer.best_fitness().unwrap() < 11.0);
+    }
+
+    #[test]
+    fn test_place_respects_bin_quantities() {
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_description(rectangle(1.0, 1.0), Some(2), 1.0)
+            .piece_description(rectangle(1.0, 1.0), vec![0.0], 3)
+            .build()
+            .unwrap();
+        let packer = GeneticIrregularBinPacker::builder()
+            .problem(problem)
+            .population_size(1)
+            .build()
+            .unwrap();
+        let solution = packer.place(&packer.population()[0]);
+        assert_eq!(solution.placements().len(), 2);
+        assert_eq!(solution.bins_used(), 2);
     }
 }
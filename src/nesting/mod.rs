@@ -1,2 +1,3 @@
 pub mod problem;
-pub mod genetic;
\ No newline at end of file
+pub mod genetic;
+pub mod validation;
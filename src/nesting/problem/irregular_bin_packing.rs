@@ -6,39 +6,360 @@ use crate::multi_polygon::MultiPolygon;
 use crate::point::Point2D;
 use crate::polygon::Polygon;
 use anyhow::{anyhow, Result};
+use num_traits::{Float, NumCast, One, Zero};
+use std::f64::consts::PI;
+
+/// How a piece may be rotated, angles are in radians
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RotationPolicy<T> {
+    /// Only the given rotation
+    Fixed(T),
+    /// Any of the given rotations
+    Discrete(Vec<T>),
+    /// Any multiple of the given angle, eg. every 90 degrees
+    Step(T),
+    /// Any rotation. The search starts from the multiples of the given angle
+    /// and refines the rotations locally.
+    Continuous(T),
+}
+
+impl<T: Float> RotationPolicy<T> {
+    /// Returns the rotations to start searching from,
+    /// all allowed rotations unless the policy is continuous.
+    /// A step that is not a positive number only yields no rotation.
+    pub fn candidates(&self) -> Vec<T> {
+        match self {
+            RotationPolicy::Fixed(rotation) => vec![*rotation],
+            RotationPolicy::Discrete(rotations) => rotations.clone(),
+            RotationPolicy::Step(step) | RotationPolicy::Continuous(step)
+                if !step.is_finite() || *step <= T::zero() =>
+            {
+                vec![T::zero()]
+            }
+            RotationPolicy::Step(step) | RotationPolicy::Continuous(step) => {
+                let turn = T::from(std::f64::consts::TAU).unwrap();
+                let mut rotations = vec![];
+                let mut rotation = T::zero();
+                // stop short of a full turn, it is the same as no rotation
+                while rotation < turn - *step / (T::one() + T::one()) {
+                    rotations.push(rotation);
+                    rotation = T::from(rotations.len()).unwrap() * *step;
+                }
+                rotations
+            }
+        }
+    }
+
+    /// Returns true if any rotation is allowed
+    pub fn is_continuous(&self) -> bool {
+        matches!(self, RotationPolicy::Continuous(_))
+    }
+
+    /// Returns true if the policy allows the rotation, up to whole turns and the given tolerance
+    pub fn allows(&self, rotation: T, tolerance: T) -> bool {
+        let turn = T::from(std::f64::consts::TAU).unwrap();
+        let same = |a: T, b: T| {
+            let difference = (a - b) % turn;
+            difference.abs().min(turn - difference.abs()) <= tolerance
+        };
+        match self {
+            RotationPolicy::Fixed(allowed) => same(rotation, *allowed),
+            RotationPolicy::Discrete(allowed) => allowed.iter().any(|a| same(rotation, *a)),
+            RotationPolicy::Step(step) => {
+                // the last multiple before a full turn may not divide it, so also try no rotation
+                let nearest = (rotation % turn / *step).round() * *step;
+                same(rotation, nearest) || same(rotation, T::zero())
+            }
+            RotationPolicy::Continuous(_) => true,
+        }
+    }
+}
+
+impl<T: Zero> Default for RotationPolicy<T> {
+    /// The piece is not rotated
+    fn default() -> Self {
+        RotationPolicy::Fixed(T::zero())
+    }
+}
+
+impl<T> From<Vec<T>> for RotationPolicy<T> {
+    /// Allows the given rotations
+    fn from(rotations: Vec<T>) -> Self {
+        RotationPolicy::Discrete(rotations)
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for RotationPolicy<T> {
+    fn from(rotations: [T; N]) -> Self {
+        Vec::from(rotations).into()
+    }
+}
+
+/// The grain of a piece, eg. of fabric or wood, and how closely it has to follow the grain of the bin
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: serde::Serialize, T::Value: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, T::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Grain<T: Point2D> {
+    /// the direction of the grain in the local frame of the piece
+    pub direction: T,
+    /// how far the grain may deviate from the grain of the bin, in radians
+    #[cfg_attr(feature = "serde", serde(default = "Zero::zero"))]
+    pub tolerance: T::Value,
+    /// the grain may also run against the grain of the bin, the piece turned by half a turn
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub allow_reverse: bool,
+}
+
+impl<T: Point2D> Grain<T> {
+    pub fn new(direction: T, tolerance: T::Value, allow_reverse: bool) -> Self {
+        Self {
+            direction,
+            tolerance,
+            allow_reverse,
+        }
+    }
+
+    /// Returns the direction of the grain once the piece is mirrored and rotated
+    fn placed_direction(&self, rotation: T::Value, mirrored: bool) -> T {
+        let direction = if mirrored {
+            self.direction.reflect()
+        } else {
+            self.direction
+        };
+        direction.rotate(rotation)
+    }
+
+    /// Returns true if the grain stays within the tolerance of the bin grain
+    /// with the piece mirrored and rotated
+    pub fn allows(&self, rotation: T::Value, mirrored: bool, bin_grain: T) -> bool {
+        let direction = self.placed_direction(rotation, mirrored);
+        let cross = direction.x() * bin_grain.y() - direction.y() * bin_grain.x();
+        let deviation = cross.atan2(direction.dot(&bin_grain)).abs();
+        let tolerance = self.tolerance + T::epsilon();
+        let half_turn = <T::Value as NumCast>::from(PI).unwrap();
+        deviation <= tolerance || (self.allow_reverse && half_turn - deviation <= tolerance)
+    }
+
+    /// Returns the rotations, in [0, 2π), that align the grain exactly with the bin grain
+    /// with the piece mirrored or not
+    pub fn aligned_rotations(&self, bin_grain: T, mirrored: bool) -> Vec<T::Value> {
+        let direction = self.placed_direction(Zero::zero(), mirrored);
+        let half_turn = <T::Value as NumCast>::from(PI).unwrap();
+        let turn = half_turn + half_turn;
+        let aligned = bin_grain.y().atan2(bin_grain.x()) - direction.y().atan2(direction.x());
+        let mut rotations = vec![aligned];
+        if self.allow_reverse {
+            rotations.push(aligned + half_turn);
+        }
+        rotations
+            .into_iter()
+            .map(|rotation| {
+                let rotation = rotation % turn;
+                if rotation < Zero::zero() {
+                    rotation + turn
+                } else {
+                    rotation
+                }
+            })
+            .collect()
+    }
+}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P: serde::Serialize, P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct PieceDescription<P: Polygon> {
     pub piece: MultiPolygon<P>,
-    pub allowed_rotations: Vec<<P::Point as Point2D>::Value>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rotations: RotationPolicy<<P::Point as Point2D>::Value>,
     pub instances: usize,
+    /// the piece may be flipped over, mirrored across its local y axis before it is rotated
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub allow_mirror: bool,
+    /// the grain of the piece, None if the piece can be placed across the grain of the bin
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub grain: Option<Grain<P::Point>>,
 }
 
 impl<P: Polygon> PieceDescription<P> {
-    pub fn new(
-        piece: MultiPolygon<P>,
-        allowed_rotations: Vec<<P::Point as Point2D>::Value>,
-        instances: usize,
-    ) -> Self {
+    pub fn new<R>(piece: MultiPolygon<P>, rotations: R, instances: usize) -> Self
+    where
+        R: Into<RotationPolicy<<P::Point as Point2D>::Value>>,
+    {
         Self {
             piece,
-            allowed_rotations,
+            rotations: rotations.into(),
             instances,
+            allow_mirror: false,
+            grain: None,
+        }
+    }
+
+    /// Returns true if the piece may be flipped over and its polygons can be mirrored
+    pub fn may_mirror(&self) -> bool {
+        self.allow_mirror && P::can_mirror()
+    }
+
+    /// Returns the description with the piece aligned to the grain of the bins
+    pub fn with_grain(mut self, grain: Grain<P::Point>) -> Self {
+        self.grain = Some(grain);
+        self
+    }
+
+    /// Returns true if the rotation keeps the grain of the piece
+    /// within its tolerance of the grain of a bin, always true for pieces without grain
+    pub fn keeps_grain(
+        &self,
+        rotation: <P::Point as Point2D>::Value,
+        mirrored: bool,
+        bin_grain: P::Point,
+    ) -> bool {
+        self.grain
+            .as_ref()
+            .is_none_or(|grain| grain.allows(rotation, mirrored, bin_grain))
+    }
+
+    /// Returns the rotations to start searching from in a bin with the given grain,
+    /// the candidates of the rotation policy that keep the grain.
+    /// Continuous rotations also start from the rotations aligning the grain exactly.
+    pub fn rotation_candidates(
+        &self,
+        bin_grain: P::Point,
+        mirrored: bool,
+    ) -> Vec<<P::Point as Point2D>::Value> {
+        let mut candidates = self.rotations.candidates();
+        if let Some(grain) = &self.grain {
+            candidates.retain(|rotation| grain.allows(*rotation, mirrored, bin_grain));
+            if self.rotations.is_continuous() {
+                for aligned in grain.aligned_rotations(bin_grain, mirrored) {
+                    if !candidates.iter().any(|rotation| {
+                        (*rotation - aligned).abs() <= <P::Point as Point2D>::epsilon()
+                    }) {
+                        candidates.push(aligned);
+                    }
+                }
+            }
         }
+        candidates
     }
 }
 
+/// A type of bin, eg. a stock sheet size or a remnant
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P: serde::Serialize, P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct BinDescription<P: Polygon> {
+    pub bin: MultiPolygon<P>,
+    /// how many bins of this type are available, None for as many as needed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quantity: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default = "One::one"))]
+    pub cost: <P::Point as Point2D>::Value,
+    /// the direction of the grain of the bin, None for along the x axis
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub grain: Option<P::Point>,
+}
+
+impl<P: Polygon> BinDescription<P> {
+    pub fn new(
+        bin: MultiPolygon<P>,
+        quantity: Option<usize>,
+        cost: <P::Point as Point2D>::Value,
+    ) -> Self {
+        Self {
+            bin,
+            quantity,
+            cost,
+            grain: None,
+        }
+    }
+
+    /// Returns the description with the grain of the bin running in the given direction
+    pub fn with_grain(mut self, grain: P::Point) -> Self {
+        self.grain = Some(grain);
+        self
+    }
+
+    /// Returns the direction of the grain of the bin
+    pub fn grain_direction(&self) -> P::Point {
+        self.grain
+            .unwrap_or_else(|| P::Point::from_xy(One::one(), Zero::zero()))
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P: serde::Serialize, P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    )),
+    serde(try_from = "IrregularBinPackingProblemRaw<P>")
+)]
 pub struct IrregularBinPackingProblem<P: Polygon> {
-    bin: MultiPolygon<P>,
+    bin_descriptions: Vec<BinDescription<P>>,
+    piece_descriptions: Vec<PieceDescription<P>>,
+    #[cfg_attr(feature = "serde", serde(default = "Zero::zero"))]
+    spacing: <P::Point as Point2D>::Value,
+}
+
+/// A problem as it is deserialized, before the checks of the builder
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+    deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+))]
+struct IrregularBinPackingProblemRaw<P: Polygon> {
+    bin_descriptions: Vec<BinDescription<P>>,
     piece_descriptions: Vec<PieceDescription<P>>,
+    #[serde(default = "Zero::zero")]
+    spacing: <P::Point as Point2D>::Value,
+}
+
+#[cfg(feature = "serde")]
+impl<P: Polygon> TryFrom<IrregularBinPackingProblemRaw<P>> for IrregularBinPackingProblem<P> {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: IrregularBinPackingProblemRaw<P>) -> Result<Self> {
+        Self::builder()
+            .bin_descriptions(raw.bin_descriptions)
+            .piece_descriptions(raw.piece_descriptions)
+            .spacing(raw.spacing)
+            .build()
+    }
 }
 
 impl<P: Polygon> IrregularBinPackingProblem<P> {
+    /// Creates a problem with as many bins of a single type as needed, each costing one
     pub fn new(bin: MultiPolygon<P>, piece_descriptions: Vec<PieceDescription<P>>) -> Self {
         Self {
-            bin,
+            bin_descriptions: vec![BinDescription::new(bin, None, One::one())],
             piece_descriptions,
+            spacing: Zero::zero(),
         }
     }
 
@@ -47,21 +368,33 @@ impl<P: Polygon> IrregularBinPackingProblem<P> {
         IrregularBinPackingProblemBuilder::new()
     }
 
-    /// Get the shape to pack all pieces into
+    /// Get the shape of the first bin type, the shape to pack all pieces into
+    /// when there is a single bin type
     pub fn bin(&self) -> &MultiPolygon<P> {
-        &self.bin
+        &self.bin_descriptions[0].bin
+    }
+
+    /// Get the types of bins the pieces can be packed into
+    pub fn bin_descriptions(&self) -> &Vec<BinDescription<P>> {
+        &self.bin_descriptions
     }
 
     /// Get the pieces that should be packed into the bin
     pub fn piece_descriptions(&self) -> &Vec<PieceDescription<P>> {
         &self.piece_descriptions
     }
+
+    /// Get the minimum gap between pieces and between pieces and the bin boundary
+    pub fn spacing(&self) -> <P::Point as Point2D>::Value {
+        self.spacing
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct IrregularBinPackingProblemBuilder<P: Polygon> {
-    bin: Option<MultiPolygon<P>>,
+    bin_descriptions: Vec<BinDescription<P>>,
     piece_descriptions: Vec<PieceDescription<P>>,
+    spacing: <P::Point as Point2D>::Value,
 }
 
 impl<P: Polygon> IrregularBinPackingProblemBuilder<P> {
@@ -69,14 +402,37 @@ impl<P: Polygon> IrregularBinPackingProblemBuilder<P> {
     /// use the build method to consume the builder and return a problem
     pub fn new() -> Self {
         Self {
-            bin: None,
+            bin_descriptions: vec![],
             piece_descriptions: vec![],
+            spacing: Zero::zero(),
         }
     }
 
-    /// Sets the bin to pack the pieces into
+    /// Sets the bin to pack the pieces into, as many as needed, each costing one
     pub fn bin(mut self, bin: MultiPolygon<P>) -> Self {
-        self.bin = Some(bin);
+        self.bin_descriptions = vec![BinDescription::new(bin, None, One::one())];
+        self
+    }
+
+    /// Sets the types of bins to pack the pieces into
+    pub fn bin_descriptions<I>(mut self, bin_descriptions: I) -> Self
+    where
+        I: IntoIterator<Item = BinDescription<P>>,
+    {
+        self.bin_descriptions = bin_descriptions.into_iter().collect();
+        self
+    }
+
+    /// Adds a type of bin to pack the pieces into,
+    /// `quantity` bins of it are available, or as many as needed if None
+    pub fn bin_description(
+        mut self,
+        bin: MultiPolygon<P>,
+        quantity: Option<usize>,
+        cost: <P::Point as Point2D>::Value,
+    ) -> Self {
+        self.bin_descriptions
+            .push(BinDescription::new(bin, quantity, cost));
         self
     }
 
@@ -89,54 +445,209 @@ impl<P: Polygon> IrregularBinPackingProblemBuilder<P> {
         self
     }
 
-    /// Adds a piece to pack into the bin
-    pub fn piece_description<I>(
+    /// Adds a piece to pack into the bin.
+    /// The rotations are a policy or a list of allowed rotations.
+    pub fn piece_description<R>(
         mut self,
         piece: MultiPolygon<P>,
-        allowed_rotations: I,
+        rotations: R,
         instances: usize,
     ) -> Self
     where
-        I: IntoIterator<Item = <P::Point as Point2D>::Value>,
+        R: Into<RotationPolicy<<P::Point as Point2D>::Value>>,
     {
-        self.piece_descriptions.push(PieceDescription::new(
-            piece,
-            allowed_rotations.into_iter().collect(),
-            instances,
-        ));
+        self.piece_descriptions
+            .push(PieceDescription::new(piece, rotations, instances));
+        self
+    }
+
+    /// Sets the minimum gap to keep between pieces and between pieces and the bin boundary,
+    /// eg. the kerf of the cutting tool. Defaults to zero.
+    pub fn spacing(mut self, spacing: <P::Point as Point2D>::Value) -> Self {
+        self.spacing = spacing;
         self
     }
 
     /// Returns a problem
     pub fn build(self) -> Result<IrregularBinPackingProblem<P>> {
+        if self.spacing < Zero::zero() || self.spacing.is_nan() {
+            return Err(anyhow!("Spacing must not be negative"));
+        }
+        if self.bin_descriptions.is_empty() {
+            return Err(anyhow!("No bin set"));
+        }
+        if self
+            .bin_descriptions
+            .iter()
+            .any(|description| description.cost < Zero::zero() || description.cost.is_nan())
+        {
+            return Err(anyhow!("Bin costs must not be negative"));
+        }
+        if self
+            .bin_descriptions
+            .iter()
+            .any(|description| description.quantity == Some(0))
+        {
+            return Err(anyhow!("Bin quantities must be at least one"));
+        }
+        check_piece_descriptions(&self.piece_descriptions)?;
         Ok(IrregularBinPackingProblem {
-            bin: self.bin.ok_or(anyhow!("No bin set"))?,
+            bin_descriptions: self.bin_descriptions,
             piece_descriptions: self.piece_descriptions,
+            spacing: self.spacing,
         })
     }
 }
 
+/// Returns an error if the rotation policy of a piece allows no rotation,
+/// if a piece may be mirrored but its polygons cannot be
+/// or if the grain of a piece has no direction
+pub(crate) fn check_piece_descriptions<P: Polygon>(
+    piece_descriptions: &[PieceDescription<P>],
+) -> Result<()> {
+    for (piece_id, description) in piece_descriptions.iter().enumerate() {
+        match &description.rotations {
+            RotationPolicy::Discrete(rotations) if rotations.is_empty() => {
+                return Err(anyhow!("Piece {} allows no rotation", piece_id));
+            }
+            RotationPolicy::Step(step) | RotationPolicy::Continuous(step)
+                if !step.is_finite() || *step <= Zero::zero() =>
+            {
+                return Err(anyhow!(
+                    "Rotation step of piece {} must be positive",
+                    piece_id
+                ));
+            }
+            _ => {}
+        }
+        if description.allow_mirror && !P::can_mirror() {
+            return Err(anyhow!(
+                "Piece {} may be mirrored but its polygons cannot be mirrored",
+                piece_id
+            ));
+        }
+        if let Some(grain) = &description.grain {
+            if grain.direction.dot(&grain.direction) == Zero::zero() {
+                return Err(anyhow!("Grain of piece {} has no direction", piece_id));
+            }
+            if grain.tolerance < Zero::zero() || grain.tolerance.is_nan() {
+                return Err(anyhow!(
+                    "Grain tolerance of piece {} must not be negative",
+                    piece_id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct IrregularBinPackingSolution<P: Polygon> {
     placements: Vec<IrregularBinPackingPlacement<P>>,
+    /// the bin type of each bin, indexed by bin id
+    #[cfg_attr(feature = "serde", serde(default))]
+    bin_types: Vec<usize>,
 }
 
 impl <P: Polygon> IrregularBinPackingSolution<P> {
+    /// Creates a solution where every bin is of the first bin type
     pub fn new(placements: Vec<IrregularBinPackingPlacement<P>>) -> Self {
         Self {
-            placements
+            placements,
+            bin_types: vec![],
         }
     }
 
+    /// Creates a solution with the bin type of each bin, indexed by bin id
+    pub fn with_bin_types(
+        placements: Vec<IrregularBinPackingPlacement<P>>,
+        bin_types: Vec<usize>,
+    ) -> Self {
+        Self {
+            placements,
+            bin_types,
+        }
+    }
+
+    /// Returns the index into the problem's bin descriptions of the bin's type
+    pub fn bin_type(&self, bin_id: usize) -> usize {
+        self.bin_types.get(bin_id).copied().unwrap_or(0)
+    }
+
     pub fn placements(&self) -> &Vec<IrregularBinPackingPlacement<P>> {
         &self.placements
     }
+
+    /// Returns the number of bins that have at least one piece in them
+    pub fn bins_used(&self) -> usize {
+        self.placements
+            .iter()
+            .map(|placement| placement.bin_id + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the fraction of each bin's area covered by pieces, indexed by bin id
+    pub fn bin_utilization(
+        &self,
+        problem: &IrregularBinPackingProblem<P>,
+    ) -> Vec<<P::Point as Point2D>::Value> {
+        let mut utilization = vec![Zero::zero(); self.bins_used()];
+        for placement in self.placements.iter() {
+            utilization[placement.bin_id] +=
+                problem.piece_descriptions()[placement.piece_id].piece.area();
+        }
+        for (bin_id, u) in utilization.iter_mut().enumerate() {
+            *u = *u / problem.bin_descriptions()[self.bin_type(bin_id)].bin.area();
+        }
+        utilization
+    }
+
+    /// Returns the summed cost of the bins that have at least one piece in them
+    pub fn total_cost(
+        &self,
+        problem: &IrregularBinPackingProblem<P>,
+    ) -> <P::Point as Point2D>::Value {
+        (0..self.bins_used())
+            .map(|bin_id| problem.bin_descriptions()[self.bin_type(bin_id)].cost)
+            .fold(Zero::zero(), |total, cost| total + cost)
+    }
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    ))
+)]
 pub struct IrregularBinPackingPlacement<P: Polygon> {
     bin_id: usize,
     piece_id: usize,
     location: P::Point,
     rotation: <P::Point as Point2D>::Value,
+    /// index into the solution's placements of the piece this one is nested in a hole of
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    parent: Option<usize>,
+    /// the piece is mirrored across its local y axis before it is rotated
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    mirrored: bool,
 }
 
 impl<P: Polygon> IrregularBinPackingPlacement<P> {
@@ -151,6 +662,8 @@ impl<P: Polygon> IrregularBinPackingPlacement<P> {
             piece_id,
             location,
             rotation,
+            parent: None,
+            mirrored: false,
         }
     }
 
@@ -169,4 +682,116 @@ impl<P: Polygon> IrregularBinPackingPlacement<P> {
     pub fn rotation(&self) -> <P::Point as Point2D>::Value {
         self.rotation
     }
+
+    /// Get the index of the placement whose piece this one sits in a hole of,
+    /// none if the piece is placed directly in the bin
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Sets the index of the placement whose piece this one sits in a hole of
+    pub fn set_parent(&mut self, parent: Option<usize>) {
+        self.parent = parent;
+    }
+
+    /// Returns true if the piece is flipped over
+    pub fn mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Sets whether the piece is flipped over, mirrored across its local y axis before it is rotated
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
+    /// Returns a copy of the piece mirrored, rotated and moved to this placement.
+    /// Panics if the placement is mirrored but polygons of type `P` cannot be mirrored,
+    /// solutions of the packers never are.
+    pub fn place_piece(&self, piece: &MultiPolygon<P>) -> MultiPolygon<P> {
+        let mut piece = piece.clone();
+        piece.for_each_polygon(|p| {
+            p.set_mirrored(self.mirrored);
+            p.set_rotation(self.rotation);
+            p.set_offset(self.location);
+        });
+        piece
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{Point2D, Polygon};
+
+    #[test]
+    fn test_json_round_trip() {
+        // the example of the README
+        let json = r#"{
+            "bin_descriptions": [
+                {"bin": {"outer": {"vertices": [{"x": 0, "y": 0}, {"x": 10, "y": 0}, {"x": 10, "y": 10}, {"x": 0, "y": 10}]}}},
+                {"bin": {"outer": {"vertices": [{"x": 0, "y": 0}, {"x": 5, "y": 0}, {"x": 5, "y": 5}, {"x": 0, "y": 5}]}}, "quantity": 1, "cost": 0.2}
+            ],
+            "piece_descriptions": [{
+                "piece": {
+                    "outer": {"vertices": [{"x": 0, "y": 0}, {"x": 4, "y": 0}, {"x": 4, "y": 4}, {"x": 0, "y": 4}]},
+                    "holes": [{"vertices": [{"x": 1, "y": 1}, {"x": 3, "y": 1}, {"x": 3, "y": 3}, {"x": 1, "y": 3}]}]
+                },
+                "rotations": {"discrete": [0.0, 1.5707963267948966]},
+                "instances": 2,
+                "allow_mirror": true
+            }],
+            "spacing": 0.5
+        }"#;
+        let problem: IrregularBinPackingProblem<Polygon> = serde_json::from_str(json).unwrap();
+        assert_eq!(problem.piece_descriptions()[0].instances, 2);
+        assert!(problem.piece_descriptions()[0].allow_mirror);
+        assert_eq!(problem.spacing(), 0.5);
+        assert_eq!(problem.bin_descriptions()[0].quantity, None);
+        assert_eq!(problem.bin_descriptions()[0].cost, 1.0);
+        assert_eq!(problem.bin_descriptions()[1].quantity, Some(1));
+        approx::assert_abs_diff_eq!(problem.piece_descriptions()[0].piece.area(), 12.0);
+
+        let problem: IrregularBinPackingProblem<Polygon> =
+            serde_json::from_str(&serde_json::to_string(&problem).unwrap()).unwrap();
+        approx::assert_abs_diff_eq!(problem.bin().area(), 100.0);
+
+        let solution = IrregularBinPackingSolution::<Polygon>::with_bin_types(
+            vec![
+                IrregularBinPackingPlacement::new(0, 0, Point2D { x: 1.0, y: 2.0 }, 0.0),
+                IrregularBinPackingPlacement::new(1, 0, Point2D { x: 0.5, y: 0.5 }, 0.0),
+            ],
+            vec![0, 1],
+        );
+        let json = serde_json::to_value(&solution).unwrap();
+        assert_eq!(
+            json["placements"][1],
+            serde_json::json!({"bin_id": 1, "piece_id": 0, "location": {"x": 0.5, "y": 0.5}, "rotation": 0.0})
+        );
+        assert_eq!(json["bin_types"], serde_json::json!([0, 1]));
+        let solution: IrregularBinPackingSolution<Polygon> = serde_json::from_value(json).unwrap();
+        assert_eq!(solution.bins_used(), 2);
+        assert_eq!(solution.total_cost(&problem), 1.2);
+        approx::assert_abs_diff_eq!(solution.bin_utilization(&problem)[1], 12.0 / 25.0);
+    }
+
+    #[test]
+    fn test_json_is_checked_like_the_builder() {
+        let square = r#"{"outer": {"vertices": [{"x": 0, "y": 0}, {"x": 4, "y": 0}, {"x": 4, "y": 4}, {"x": 0, "y": 4}]}}"#;
+        let problem = |bins: &str, rotations: &str, spacing: &str| {
+            serde_json::from_str::<IrregularBinPackingProblem<Polygon>>(&format!(
+                r#"{{"bin_descriptions": [{}], "piece_descriptions": [{{"piece": {}, "rotations": {}, "instances": 1}}], "spacing": {}}}"#,
+                bins, square, rotations, spacing
+            ))
+        };
+        let bin = format!(r#"{{"bin": {}}}"#, square);
+        assert!(problem(&bin, r#"{"step": 1.0}"#, "0.5").is_ok());
+        assert!(problem("", r#"{"step": 1.0}"#, "0.5").is_err());
+        assert!(problem(&bin, r#"{"step": 0.0}"#, "0.5").is_err());
+        assert!(problem(&bin, r#"{"discrete": []}"#, "0.5").is_err());
+        assert!(problem(&bin, r#"{"step": 1.0}"#, "-0.5").is_err());
+        let bin = format!(r#"{{"bin": {}, "cost": -1}}"#, square);
+        assert!(problem(&bin, r#"{"step": 1.0}"#, "0.5").is_err());
+        let bin = format!(r#"{{"bin": {}, "quantity": 0}}"#, square);
+        assert!(problem(&bin, r#"{"step": 1.0}"#, "0.5").is_err());
+    }
 }
@@ -1,3 +1,5 @@
 pub mod irregular_bin_packing;
+pub mod strip_packing;
 
-pub use irregular_bin_packing::*;
\ No newline at end of file
+pub use irregular_bin_packing::*;
+pub use strip_packing::*;
@@ -0,0 +1,440 @@
+//! Strip packing places all pieces on a sheet of fixed width and open ended length,
+//! eg. fabric or sheet metal from a roll, and tries to use as little length as possible.
+//! The strip runs along the positive x axis from x = 0, its width along the y axis.
+
+use super::irregular_bin_packing::check_piece_descriptions;
+use super::{
+    IrregularBinPackingPlacement, IrregularBinPackingProblem, PieceDescription, RotationPolicy,
+};
+use crate::multi_polygon::MultiPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::{Float, NumCast, One, Zero};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P: serde::Serialize, P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    )),
+    serde(try_from = "StripPackingProblemRaw<P>")
+)]
+pub struct StripPackingProblem<P: Polygon> {
+    width: <P::Point as Point2D>::Value,
+    piece_descriptions: Vec<PieceDescription<P>>,
+    #[cfg_attr(feature = "serde", serde(default = "Zero::zero"))]
+    spacing: <P::Point as Point2D>::Value,
+}
+
+/// A problem as it is deserialized, before the checks of the builder
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+    deserialize = "P: serde::Deserialize<'de>, P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+))]
+struct StripPackingProblemRaw<P: Polygon> {
+    width: <P::Point as Point2D>::Value,
+    piece_descriptions: Vec<PieceDescription<P>>,
+    #[serde(default = "Zero::zero")]
+    spacing: <P::Point as Point2D>::Value,
+}
+
+#[cfg(feature = "serde")]
+impl<P: Polygon> TryFrom<StripPackingProblemRaw<P>> for StripPackingProblem<P> {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: StripPackingProblemRaw<P>) -> Result<Self> {
+        Self::builder()
+            .width(raw.width)
+            .piece_descriptions(raw.piece_descriptions)
+            .spacing(raw.spacing)
+            .build()
+    }
+}
+
+impl<P: Polygon> StripPackingProblem<P> {
+    /// Returns a new builder instance for constructing a problem
+    pub fn builder() -> StripPackingProblemBuilder<P> {
+        StripPackingProblemBuilder::new()
+    }
+
+    /// Get the width of the strip
+    pub fn width(&self) -> <P::Point as Point2D>::Value {
+        self.width
+    }
+
+    /// Get the pieces that should be packed onto the strip
+    pub fn piece_descriptions(&self) -> &Vec<PieceDescription<P>> {
+        &self.piece_descriptions
+    }
+
+    /// Get the minimum gap between pieces and between pieces and the strip boundary
+    pub fn spacing(&self) -> <P::Point as Point2D>::Value {
+        self.spacing
+    }
+
+    /// Returns the length of strip all pieces fit on in a row, whatever their rotation,
+    /// with the spacing before every piece and after the last one.
+    /// It is never shorter than the width.
+    pub fn max_length(&self) -> <P::Point as Point2D>::Value {
+        let two = <P::Point as Point2D>::Value::one() + One::one();
+        self.piece_descriptions
+            .iter()
+            .map(|description| {
+                // rotating about the local origin keeps the piece within this radius
+                let radius = description
+                    .piece
+                    .outer()
+                    .iter_vertices_local()
+                    .map(|vertex| vertex.dot(vertex).sqrt())
+                    .fold(Zero::zero(), Float::max);
+                (radius * two + self.spacing)
+                    * <<P::Point as Point2D>::Value as NumCast>::from(description.instances)
+                        .unwrap()
+            })
+            .fold(self.spacing, |total, length| total + length)
+            .max(self.width)
+    }
+}
+
+impl<P: Polygon + From<Vec<P::Point>>> StripPackingProblem<P> {
+    /// Returns a bin packing problem with a single bin, the strip cut at `max_length`
+    pub fn to_bin_packing_problem(&self) -> IrregularBinPackingProblem<P> {
+        let (zero, length) = (Zero::zero(), self.max_length());
+        IrregularBinPackingProblem::builder()
+            .bin(MultiPolygon::new(
+                P::from(vec![
+                    P::Point::from_xy(zero, zero),
+                    P::Point::from_xy(zero, self.width),
+                    P::Point::from_xy(length, self.width),
+                    P::Point::from_xy(length, zero),
+                ]),
+                vec![],
+            ))
+            .piece_descriptions(self.piece_descriptions.clone())
+            .spacing(self.spacing)
+            .build()
+            .unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StripPackingProblemBuilder<P: Polygon> {
+    width: Option<<P::Point as Point2D>::Value>,
+    piece_descriptions: Vec<PieceDescription<P>>,
+    spacing: <P::Point as Point2D>::Value,
+}
+
+impl<P: Polygon> StripPackingProblemBuilder<P> {
+    /// Creates a new builder instance.
+    /// use the build method to consume the builder and return a problem
+    pub fn new() -> Self {
+        Self {
+            width: None,
+            piece_descriptions: vec![],
+            spacing: Zero::zero(),
+        }
+    }
+
+    /// Sets the width of the strip
+    pub fn width(mut self, width: <P::Point as Point2D>::Value) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets the pieces to be packed onto the strip
+    pub fn piece_descriptions<I>(mut self, piece_descriptions: I) -> Self
+    where
+        I: IntoIterator<Item = PieceDescription<P>>,
+    {
+        self.piece_descriptions = piece_descriptions.into_iter().collect();
+        self
+    }
+
+    /// Adds a piece to pack onto the strip.
+    /// The rotations are a policy or a list of allowed rotations.
+    pub fn piece_description<R>(
+        mut self,
+        piece: MultiPolygon<P>,
+        rotations: R,
+        instances: usize,
+    ) -> Self
+    where
+        R: Into<RotationPolicy<<P::Point as Point2D>::Value>>,
+    {
+        self.piece_descriptions
+            .push(PieceDescription::new(piece, rotations, instances));
+        self
+    }
+
+    /// Sets the minimum gap to keep between pieces and between pieces and the strip boundary.
+    /// Defaults to zero.
+    pub fn spacing(mut self, spacing: <P::Point as Point2D>::Value) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Returns a problem
+    pub fn build(self) -> Result<StripPackingProblem<P>> {
+        let width = self.width.ok_or(anyhow!("No width provided"))?;
+        if width <= Zero::zero() || width.is_nan() {
+            return Err(anyhow!("Width must be positive"));
+        }
+        if self.spacing < Zero::zero() || self.spacing.is_nan() {
+            return Err(anyhow!("Spacing must not be negative"));
+        }
+        check_piece_descriptions(&self.piece_descriptions)?;
+        Ok(StripPackingProblem {
+            width,
+            piece_descriptions: self.piece_descriptions,
+            spacing: self.spacing,
+        })
+    }
+}
+
+impl<P: Polygon> Default for StripPackingProblemBuilder<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Polygon> From<IrregularBinPackingProblem<P>> for StripPackingProblemBuilder<P> {
+    /// Starts a strip packing problem with the pieces and spacing of a bin packing problem
+    fn from(problem: IrregularBinPackingProblem<P>) -> Self {
+        Self::new()
+            .spacing(problem.spacing())
+            .piece_descriptions(problem.piece_descriptions().clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P::Point: serde::Serialize, <P::Point as Point2D>::Value: serde::Serialize",
+        deserialize = "P::Point: serde::Deserialize<'de>, <P::Point as Point2D>::Value: serde::Deserialize<'de>"
+    ))
+)]
+pub struct StripPackingSolution<P: Polygon> {
+    placements: Vec<IrregularBinPackingPlacement<P>>,
+}
+
+impl<P: Polygon> StripPackingSolution<P> {
+    /// Creates a solution, the bin ids of the placements are ignored
+    pub fn new(placements: Vec<IrregularBinPackingPlacement<P>>) -> Self {
+        Self { placements }
+    }
+
+    pub fn placements(&self) -> &Vec<IrregularBinPackingPlacement<P>> {
+        &self.placements
+    }
+
+    /// Returns the length of strip up to the end of the last piece
+    pub fn length(&self, problem: &StripPackingProblem<P>) -> <P::Point as Point2D>::Value {
+        used_length(problem.piece_descriptions(), &self.placements)
+    }
+
+    /// Returns the fraction of the used strip covered by pieces
+    pub fn utilization(&self, problem: &StripPackingProblem<P>) -> <P::Point as Point2D>::Value {
+        let length = self.length(problem);
+        if length == Zero::zero() {
+            return Zero::zero();
+        }
+        self.placements
+            .iter()
+            .map(|placement| {
+                problem.piece_descriptions()[placement.piece_id()]
+                    .piece
+                    .area()
+            })
+            .fold(Zero::zero(), |total: <P::Point as Point2D>::Value, area| {
+                total + area
+            })
+            / (length * problem.width())
+    }
+}
+
+/// Returns the largest x coordinate of the placed pieces, zero without pieces
+pub(crate) fn used_length<P: Polygon>(
+    piece_descriptions: &[PieceDescription<P>],
+    placements: &[IrregularBinPackingPlacement<P>],
+) -> <P::Point as Point2D>::Value {
+    placements
+        .iter()
+        .map(|placement| {
+            placement
+                .place_piece(&piece_descriptions[placement.piece_id()].piece)
+                .bounding_box()
+                .max_x
+        })
+        .fold(Zero::zero(), Float::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{rectangle, Point2D, Polygon};
+    use crate::nesting::problem::IrregularBinPackingSolution;
+    use crate::nesting::validation::{validate, Violation};
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+    #[test]
+    fn test_strip_length_and_utilization() {
+        let problem = StripPackingProblem::builder()
+            .width(4.0)
+            .piece_description(rectangle(3.0, 4.0), [0.0, FRAC_PI_2], 2)
+            .build()
+            .unwrap();
+        // two radii of 5 per instance
+        assert_eq!(problem.max_length(), 20.0);
+        let bin = problem.to_bin_packing_problem();
+        approx::assert_abs_diff_eq!(bin.bin().area(), 80.0);
+
+        let solution = StripPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 0.0, y: 0.0 }, 0.0),
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 3.0, y: 0.0 }, 0.0),
+        ]);
+        approx::assert_abs_diff_eq!(solution.length(&problem), 6.0);
+        approx::assert_abs_diff_eq!(solution.utilization(&problem), 1.0);
+
+        assert!(StripPackingProblem::<Polygon>::builder()
+            .width(0.0)
+            .build()
+            .is_err());
+        let from_bins: StripPackingProblemBuilder<Polygon> = bin.into();
+        assert_eq!(
+            from_bins
+                .width(4.0)
+                .build()
+                .unwrap()
+                .piece_descriptions()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_max_length_fits_pieces_turned_to_their_widest() {
+        // a square centered on its local origin is widest along x when turned by 45 degrees
+        let square = MultiPolygon::new(
+            Polygon::from_tuples([(-1.0, -1.0), (-1.0, 1.0), (1.0, 1.0), (1.0, -1.0)]),
+            vec![],
+        );
+        let spacing = 0.5;
+        let problem = StripPackingProblem::builder()
+            .width(2.0 * SQRT_2 + 2.0 * spacing)
+            .piece_description(square, vec![FRAC_PI_4], 3)
+            .spacing(spacing)
+            .build()
+            .unwrap();
+        approx::assert_abs_diff_eq!(
+            problem.max_length(),
+            3.0 * (2.0 * SQRT_2 + spacing) + spacing,
+            epsilon = 1e-9
+        );
+
+        let bin = problem.to_bin_packing_problem();
+        let row = |overhang: f64| {
+            IrregularBinPackingSolution::new(
+                (0..3)
+                    .map(|i| {
+                        let x = spacing + SQRT_2 + i as f64 * (2.0 * SQRT_2 + spacing);
+                        let x = if i == 2 { x + overhang } else { x };
+                        let location = Point2D {
+                            x,
+                            y: spacing + SQRT_2,
+                        };
+                        IrregularBinPackingPlacement::new(0, 0, location, FRAC_PI_4)
+                    })
+                    .collect(),
+            )
+        };
+        assert_eq!(validate(&bin, &row(0.0), 1e-6), vec![]);
+        // the strip is cut right behind the spacing of the last piece
+        assert_eq!(
+            validate(&bin, &row(0.1), 1e-6),
+            vec![Violation::Spacing { a: 2, b: None }]
+        );
+    }
+
+    #[test]
+    fn test_max_length_is_at_least_the_width() {
+        let empty = StripPackingProblem::<Polygon>::builder()
+            .width(3.0)
+            .spacing(1.0)
+            .build()
+            .unwrap();
+        assert_eq!(empty.max_length(), 3.0);
+        let bin = empty.to_bin_packing_problem();
+        assert_eq!(bin.bin().bounding_box().max_x, 3.0);
+        assert_eq!(bin.spacing(), 1.0);
+        assert!(bin.piece_descriptions().is_empty());
+        let solution = StripPackingSolution::new(vec![]);
+        assert_eq!(solution.length(&empty), 0.0);
+        assert_eq!(solution.utilization(&empty), 0.0);
+
+        // a single small piece needs a length of 2 * sqrt(2) + 2 only
+        let short = StripPackingProblem::builder()
+            .width(10.0)
+            .piece_description(rectangle(1.0, 1.0), vec![0.0], 1)
+            .spacing(1.0)
+            .build()
+            .unwrap();
+        assert_eq!(short.max_length(), 10.0);
+        approx::assert_abs_diff_eq!(short.to_bin_packing_problem().bin().area(), 100.0);
+    }
+
+    #[test]
+    fn test_used_length_of_rotated_and_spaced_pieces() {
+        let problem = StripPackingProblem::builder()
+            .width(5.0)
+            .piece_description(rectangle(3.0, 1.0), vec![0.0, FRAC_PI_2], 2)
+            .spacing(1.0)
+            .build()
+            .unwrap();
+        // turned by 90 degrees the piece spans x from -1 to 0 before it is moved
+        let solution = StripPackingSolution::new(vec![
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 2.0, y: 1.0 }, FRAC_PI_2),
+            IrregularBinPackingPlacement::new(0, 0, Point2D { x: 3.0, y: 1.0 }, 0.0),
+        ]);
+        let (turned, lying) = solution.placements().split_at(1);
+        approx::assert_abs_diff_eq!(
+            used_length(problem.piece_descriptions(), turned),
+            2.0,
+            epsilon = 1e-9
+        );
+        approx::assert_abs_diff_eq!(used_length(problem.piece_descriptions(), lying), 6.0);
+
+        // the spacing after the last piece is not part of the used length
+        approx::assert_abs_diff_eq!(solution.length(&problem), 6.0);
+        approx::assert_abs_diff_eq!(solution.utilization(&problem), 0.2);
+        let placements = solution.placements().clone();
+        assert_eq!(
+            validate(
+                &problem.to_bin_packing_problem(),
+                &IrregularBinPackingSolution::new(placements),
+                1e-6
+            ),
+            vec![]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_is_checked_like_the_builder() {
+        let problem = |width: f64| {
+            serde_json::from_value::<StripPackingProblem<Polygon>>(serde_json::json!({
+                "width": width,
+                "piece_descriptions": [{"piece": {"outer": {"vertices": [
+                    {"x": 0, "y": 0}, {"x": 1, "y": 0}, {"x": 1, "y": 1}
+                ]}}, "instances": 1}]
+            }))
+        };
+        assert_eq!(problem(4.0).unwrap().width(), 4.0);
+        assert!(problem(0.0).is_err());
+    }
+}
@@ -0,0 +1,592 @@
+//! Checks that a solution satisfies the constraints of its problem.
+//!
+//! Placed pieces must lie inside the outer contour of their bin and outside its holes,
+//! must not overlap each other and must keep the spacing of the problem. Every piece is
+//! placed as many times as it has instances, with a rotation, mirroring and grain its
+//! description allows, in bins of known types and within their quantities.
+
+use super::problem::{IrregularBinPackingProblem, IrregularBinPackingSolution};
+use crate::bounding_box::BoundingBox;
+use crate::buffer::JoinType;
+use crate::clip::Clippable;
+use crate::multi_polygon::MultiPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use crate::segment::Segment;
+use num_traits::{Float, NumCast, One, Zero};
+use std::fmt;
+
+/// A constraint of the problem that a solution breaks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A placement refers to a piece description that does not exist
+    UnknownPiece { placement: usize },
+    /// A bin is of a bin type that does not exist
+    UnknownBinType { bin_id: usize },
+    /// More bins of a type are used than are available
+    BinQuantity {
+        bin_type: usize,
+        quantity: usize,
+        used: usize,
+    },
+    /// A piece is placed a different number of times than it has instances
+    InstanceCount {
+        piece_id: usize,
+        instances: usize,
+        placed: usize,
+    },
+    /// The piece of a placement sticks out of the outer contour of its bin
+    OutsideBin { placement: usize },
+    /// The piece of a placement covers part of a hole of its bin
+    InBinHole { placement: usize },
+    /// The pieces of two placements in the same bin overlap
+    Overlap { a: usize, b: usize },
+    /// The piece of a placement is closer than the spacing to another piece,
+    /// or to the boundary of its bin if `b` is None
+    Spacing { a: usize, b: Option<usize> },
+    /// The rotation of a placement is not allowed by the rotation policy of its piece
+    Rotation { placement: usize },
+    /// The piece of a placement is mirrored but may not be
+    Mirror { placement: usize },
+    /// The grain of the piece of a placement deviates too far from the grain of its bin
+    Grain { placement: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnknownPiece { placement } => {
+                write!(f, "placement {} refers to an unknown piece", placement)
+            }
+            Violation::UnknownBinType { bin_id } => {
+                write!(f, "bin {} is of an unknown bin type", bin_id)
+            }
+            Violation::BinQuantity {
+                bin_type,
+                quantity,
+                used,
+            } => write!(
+                f,
+                "{} bins of type {} are used but only {} are available",
+                used, bin_type, quantity
+            ),
+            Violation::InstanceCount {
+                piece_id,
+                instances,
+                placed,
+            } => write!(
+                f,
+                "piece {} is placed {} times but has {} instances",
+                piece_id, placed, instances
+            ),
+            Violation::OutsideBin { placement } => {
+                write!(f, "placement {} sticks out of its bin", placement)
+            }
+            Violation::InBinHole { placement } => {
+                write!(f, "placement {} covers a hole of its bin", placement)
+            }
+            Violation::Overlap { a, b } => write!(f, "placements {} and {} overlap", a, b),
+            Violation::Spacing { a, b: Some(b) } => {
+                write!(f, "placements {} and {} are too close", a, b)
+            }
+            Violation::Spacing { a, b: None } => {
+                write!(f, "placement {} is too close to its bin boundary", a)
+            }
+            Violation::Rotation { placement } => {
+                write!(
+                    f,
+                    "placement {} has a rotation that is not allowed",
+                    placement
+                )
+            }
+            Violation::Mirror { placement } => {
+                write!(f, "placement {} is mirrored but may not be", placement)
+            }
+            Violation::Grain { placement } => {
+                write!(f, "placement {} goes against the grain", placement)
+            }
+        }
+    }
+}
+
+/// Returns every constraint of the problem the solution breaks, none if the solution is valid.
+/// Pieces may overlap each other or the boundary of their bin by up to `tolerance`,
+/// and may come up to `tolerance` closer to each other than the spacing.
+pub fn validate<P: Clippable>(
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+    tolerance: <P::Point as Point2D>::Value,
+) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    let mut bins_of_type = vec![0; problem.bin_descriptions().len()];
+    let mut known_bins = vec![true; solution.bins_used()];
+    for (bin_id, known) in known_bins.iter_mut().enumerate() {
+        match bins_of_type.get_mut(solution.bin_type(bin_id)) {
+            Some(used) => *used += 1,
+            None => {
+                *known = false;
+                violations.push(Violation::UnknownBinType { bin_id });
+            }
+        }
+    }
+    for (bin_type, (description, used)) in problem
+        .bin_descriptions()
+        .iter()
+        .zip(bins_of_type)
+        .enumerate()
+    {
+        match description.quantity {
+            Some(quantity) if used > quantity => violations.push(Violation::BinQuantity {
+                bin_type,
+                quantity,
+                used,
+            }),
+            _ => {}
+        }
+    }
+
+    // pieces shrink and bins grow by half of the tolerance, so smaller overlaps disappear
+    let half = tolerance / (<P::Point as Point2D>::Value::one() + One::one());
+    let join = JoinType::Miter(<<P::Point as Point2D>::Value as NumCast>::from(4.0).unwrap());
+    let bins = problem
+        .bin_descriptions()
+        .iter()
+        .map(|description| {
+            if half > Zero::zero() {
+                description.bin.buffer(half, join)
+            } else {
+                vec![description.bin.clone()]
+            }
+        })
+        .collect::<Vec<_>>();
+    let min_distance = problem.spacing() - tolerance;
+    let gap = min_distance.max(Zero::zero());
+
+    let mut placed = vec![0; problem.piece_descriptions().len()];
+    // the placed piece, its shrunk parts and bounds of placements with a known piece and bin
+    let mut pieces = vec![];
+    for (index, placement) in solution.placements().iter().enumerate() {
+        let Some(description) = problem.piece_descriptions().get(placement.piece_id()) else {
+            violations.push(Violation::UnknownPiece { placement: index });
+            pieces.push(None);
+            continue;
+        };
+        placed[placement.piece_id()] += 1;
+        if !description
+            .rotations
+            .allows(placement.rotation(), <P::Point as Point2D>::epsilon())
+        {
+            violations.push(Violation::Rotation { placement: index });
+        }
+        if placement.mirrored() && !description.may_mirror() {
+            violations.push(Violation::Mirror { placement: index });
+        }
+        // the geometry of a mirrored piece whose polygons cannot be mirrored can not be checked
+        if !known_bins[placement.bin_id()] || (placement.mirrored() && !P::can_mirror()) {
+            pieces.push(None);
+            continue;
+        }
+
+        let bin_type = solution.bin_type(placement.bin_id());
+        let bin = &problem.bin_descriptions()[bin_type];
+        if !description.keeps_grain(
+            placement.rotation(),
+            placement.mirrored(),
+            bin.grain_direction(),
+        ) {
+            violations.push(Violation::Grain { placement: index });
+        }
+
+        let piece = placement.place_piece(&description.piece);
+        let parts = if half > Zero::zero() {
+            piece.buffer(-half, join)
+        } else {
+            vec![piece.clone()]
+        };
+        let outside = parts
+            .iter()
+            .any(|part| !inside_outer(part, &bins[bin_type]));
+        if outside {
+            violations.push(Violation::OutsideBin { placement: index });
+        }
+        let in_hole = parts.iter().any(|part| {
+            bins[bin_type].iter().any(|region| {
+                region
+                    .holes()
+                    .iter()
+                    .any(|hole| overlaps(part, &MultiPolygon::new(hole.clone(), vec![])))
+            })
+        });
+        if in_hole {
+            violations.push(Violation::InBinHole { placement: index });
+        }
+        if !outside
+            && !in_hole
+            && min_distance > Zero::zero()
+            && distance(&piece, &bin.bin) < min_distance
+        {
+            violations.push(Violation::Spacing { a: index, b: None });
+        }
+        let bounds = piece.bounding_box();
+        pieces.push(Some((piece, parts, bounds)));
+    }
+
+    let placements = solution.placements();
+    for (a, piece_a) in pieces.iter().enumerate() {
+        let Some((piece_a, parts_a, bounds_a)) = piece_a else {
+            continue;
+        };
+        for (b, piece_b) in pieces.iter().enumerate().skip(a + 1) {
+            let Some((piece_b, parts_b, bounds_b)) = piece_b else {
+                continue;
+            };
+            if placements[a].bin_id() != placements[b].bin_id() || apart(bounds_a, bounds_b, gap) {
+                continue;
+            }
+            if parts_a
+                .iter()
+                .any(|part_a| parts_b.iter().any(|part_b| overlaps(part_a, part_b)))
+            {
+                violations.push(Violation::Overlap { a, b });
+            } else if min_distance > Zero::zero() && distance(piece_a, piece_b) < min_distance {
+                violations.push(Violation::Spacing { a, b: Some(b) });
+            }
+        }
+    }
+
+    for (piece_id, (description, placed)) in
+        problem.piece_descriptions().iter().zip(placed).enumerate()
+    {
+        if placed != description.instances {
+            violations.push(Violation::InstanceCount {
+                piece_id,
+                instances: description.instances,
+                placed,
+            });
+        }
+    }
+    violations
+}
+
+/// Returns the outer contour followed by the holes of the region
+fn contours<P: Polygon>(region: &MultiPolygon<P>) -> impl Iterator<Item = &P> + Clone {
+    std::iter::once(region.outer()).chain(region.holes())
+}
+
+/// Returns true if the point is inside the region, false if outside and None if on its boundary
+fn locate<P: Polygon>(point: &P::Point, region: &MultiPolygon<P>) -> Option<bool> {
+    match point.in_polygon(region.outer()) {
+        Some(true) => {
+            let mut location = Some(true);
+            for hole in region.holes() {
+                match point.in_polygon(hole) {
+                    Some(true) => return Some(false),
+                    None => location = None,
+                    Some(false) => {}
+                }
+            }
+            location
+        }
+        location => location,
+    }
+}
+
+/// Returns true if the outer contour of the piece lies within the outer contour of the bin
+fn inside_outer<P: Polygon>(piece: &MultiPolygon<P>, bin: &[MultiPolygon<P>]) -> bool {
+    bin.iter().any(|region| {
+        !crosses(piece.outer(), region.outer())
+            && samples(piece.outer())
+                .iter()
+                .all(|point| point.in_polygon(region.outer()) != Some(false))
+    })
+}
+
+/// Returns true if the interiors of the regions overlap
+fn overlaps<P: Polygon>(a: &MultiPolygon<P>, b: &MultiPolygon<P>) -> bool {
+    contours(a).any(|contour_a| contours(b).any(|contour_b| crosses(contour_a, contour_b)))
+        || reaches_into(a, b)
+        || reaches_into(b, a)
+}
+
+/// Returns true if the contours cross each other. `intersects_polygon` also reports
+/// contours that only touch, so a segment of each must pass through the other's interior.
+fn crosses<P: Polygon>(a: &P, b: &P) -> bool {
+    a.intersects_polygon(b)
+        && a.iter_segments().any(|segment_a| {
+            b.iter_segments()
+                .any(|segment_b| cross_properly(&segment_a, &segment_b))
+        })
+}
+
+/// Returns true if the segments cross at a point inside of both
+fn cross_properly<S: Segment>(a: &S, b: &S) -> bool {
+    let side = |segment: &S, point: &S::Point| {
+        let direction = *segment.end() - *segment.start();
+        let offset = *point - *segment.start();
+        let cross = direction.x() * offset.y() - direction.y() * offset.x();
+        if cross.abs() <= <S::Point as Point2D>::epsilon() {
+            0
+        } else if cross > Zero::zero() {
+            1
+        } else {
+            -1
+        }
+    };
+    side(a, b.start()) * side(a, b.end()) < 0 && side(b, a.start()) * side(b, a.end()) < 0
+}
+
+/// Returns the vertices of the contour and the midpoints of its edges,
+/// the midpoints catch edges cutting through a region between two of its vertices
+fn samples<P: Polygon>(contour: &P) -> Vec<P::Point> {
+    let two = <P::Point as Point2D>::Value::one() + One::one();
+    contour
+        .iter_segments()
+        .flat_map(|segment| [*segment.start(), (*segment.start() + *segment.end()) / two])
+        .collect()
+}
+
+/// Returns true if the outer contour of `a` reaches into the interior of `b`,
+/// for contours that do not cross each other
+fn reaches_into<P: Polygon>(a: &MultiPolygon<P>, b: &MultiPolygon<P>) -> bool {
+    let mut on_boundary = true;
+    for point in samples(a.outer()) {
+        match locate(&point, b) {
+            Some(true) => return true,
+            Some(false) => on_boundary = false,
+            None => {}
+        }
+    }
+    if !on_boundary {
+        return false;
+    }
+    // the whole contour lies on the boundary of b, eg. a copy placed on top of it,
+    // so a point inside of a decides
+    let two = <P::Point as Point2D>::Value::one() + One::one();
+    let bounds = a.bounding_box();
+    let center = P::Point::from_xy(
+        (bounds.min_x + bounds.max_x) / two,
+        (bounds.min_y + bounds.max_y) / two,
+    );
+    locate(&center, a) == Some(true) && locate(&center, b) == Some(true)
+}
+
+/// Returns true if the boxes are more than `gap` apart
+fn apart<T: Float>(a: &BoundingBox<T>, b: &BoundingBox<T>, gap: T) -> bool {
+    a.min_x > b.max_x + gap
+        || b.min_x > a.max_x + gap
+        || a.min_y > b.max_y + gap
+        || b.min_y > a.max_y + gap
+}
+
+/// Returns the smallest distance between the contours of the regions,
+/// for contours that do not cross each other
+fn distance<P: Polygon>(a: &MultiPolygon<P>, b: &MultiPolygon<P>) -> <P::Point as Point2D>::Value {
+    let mut distance = <P::Point as Point2D>::Value::infinity();
+    for segment_a in contours(a).flat_map(|contour| contour.iter_segments()) {
+        for segment_b in contours(b).flat_map(|contour| contour.iter_segments()) {
+            distance = distance
+                .min(point_distance(segment_a.start(), &segment_b))
+                .min(point_distance(segment_a.end(), &segment_b))
+                .min(point_distance(segment_b.start(), &segment_a))
+                .min(point_distance(segment_b.end(), &segment_a));
+        }
+    }
+    distance
+}
+
+/// Returns the distance from the point to the closest point of the segment
+fn point_distance<S: Segment>(point: &S::Point, segment: &S) -> <S::Point as Point2D>::Value {
+    let direction = *segment.end() - *segment.start();
+    let length = direction.dot(&direction);
+    let closest = if length == Zero::zero() {
+        *segment.start()
+    } else {
+        let t = ((*point - *segment.start()).dot(&direction) / length)
+            .max(Zero::zero())
+            .min(One::one());
+        *segment.start() + direction * t
+    };
+    let difference = *point - closest;
+    difference.dot(&difference).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{rectangle, Point2D, Polygon};
+    use crate::multi_polygon::MultiPolygon;
+    use crate::nesting::problem::{
+        BinDescription, Grain, IrregularBinPackingPlacement, PieceDescription, RotationPolicy,
+    };
+    use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+    #[test]
+    fn test_validate_placements() {
+        let bin = MultiPolygon::new(
+            rectangle(10.0, 10.0).outer().clone(),
+            vec![Polygon::from_tuples([
+                (4.0, 4.0),
+                (4.0, 6.0),
+                (6.0, 6.0),
+                (6.0, 4.0),
+            ])],
+        );
+        let frame = MultiPolygon::new(
+            rectangle(3.0, 3.0).outer().clone(),
+            vec![Polygon::from_tuples([
+                (0.5, 0.5),
+                (0.5, 2.5),
+                (2.5, 2.5),
+                (2.5, 0.5),
+            ])],
+        );
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_descriptions([BinDescription::new(bin, Some(1), 1.0)])
+            .piece_description(rectangle(2.0, 2.0), [0.0, FRAC_PI_2], 8)
+            .piece_description(frame, RotationPolicy::Fixed(0.0), 2)
+            .build()
+            .unwrap();
+        let placement = |bin_id, piece_id, x, y, rotation| {
+            IrregularBinPackingPlacement::new(bin_id, piece_id, Point2D { x, y }, rotation)
+        };
+        let mut mirrored = placement(0, 0, 6.5, 0.5, 0.0);
+        mirrored.set_mirrored(true);
+        let solution = IrregularBinPackingSolution::with_bin_types(
+            vec![
+                placement(0, 0, 0.0, 0.0, 0.0),
+                placement(0, 0, 3.0, 1.0, FRAC_PI_2 + TAU),
+                placement(0, 0, 9.0, 0.0, 0.0),
+                placement(0, 0, 5.0, 5.0, 0.0),
+                placement(0, 1, 7.0, 7.0, 0.0),
+                // fills the hole of the frame exactly
+                placement(0, 0, 7.5, 7.5, 0.0),
+                placement(0, 0, 2.0, 6.0, 0.3),
+                mirrored,
+                placement(2, 0, 0.0, 0.0, 0.0),
+                placement(0, 5, 0.0, 0.0, 0.0),
+            ],
+            vec![0, 0, 5],
+        );
+
+        assert_eq!(
+            validate(&problem, &solution, 0.0),
+            [
+                Violation::UnknownBinType { bin_id: 2 },
+                Violation::BinQuantity {
+                    bin_type: 0,
+                    quantity: 1,
+                    used: 2
+                },
+                Violation::OutsideBin { placement: 2 },
+                Violation::InBinHole { placement: 3 },
+                Violation::Rotation { placement: 6 },
+                Violation::Mirror { placement: 7 },
+                Violation::UnknownPiece { placement: 9 },
+                Violation::Overlap { a: 0, b: 1 },
+                Violation::InstanceCount {
+                    piece_id: 1,
+                    instances: 2,
+                    placed: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_tolerance_and_spacing() {
+        let problem = |spacing| {
+            IrregularBinPackingProblem::builder()
+                .bin(rectangle(10.0, 10.0))
+                .piece_description(rectangle(2.0, 2.0), RotationPolicy::Fixed(0.0), 2)
+                .spacing(spacing)
+                .build()
+                .unwrap()
+        };
+        let violations = |spacing, a: (f64, f64), b: (f64, f64), tolerance| {
+            let solution = IrregularBinPackingSolution::new(vec![
+                IrregularBinPackingPlacement::new(0, 0, Point2D { x: a.0, y: a.1 }, 0.0),
+                IrregularBinPackingPlacement::new(0, 0, Point2D { x: b.0, y: b.1 }, 0.0),
+            ]);
+            validate(&problem(spacing), &solution, tolerance)
+        };
+
+        assert!(violations(0.0, (0.0, 0.0), (2.0, 0.0), 0.0).is_empty());
+        assert_eq!(
+            violations(0.0, (0.0, 0.0), (1.99, 0.0), 0.0),
+            [Violation::Overlap { a: 0, b: 1 }]
+        );
+        assert!(violations(0.0, (0.0, 0.0), (1.99, 0.0), 0.05).is_empty());
+        assert_eq!(
+            violations(0.0, (-0.01, 0.0), (0.0, 8.0), 0.0),
+            [Violation::OutsideBin { placement: 0 }]
+        );
+        assert!(violations(0.0, (-0.01, 0.0), (0.0, 8.0), 0.05).is_empty());
+        // a copy on top of another piece
+        assert_eq!(
+            violations(0.0, (3.0, 3.0), (3.0, 3.0), 0.05),
+            [Violation::Overlap { a: 0, b: 1 }]
+        );
+
+        assert!(violations(1.0, (1.0, 1.0), (4.0, 1.0), 0.0).is_empty());
+        assert_eq!(
+            violations(1.0, (1.0, 1.0), (3.5, 1.0), 0.0),
+            [Violation::Spacing { a: 0, b: Some(1) }]
+        );
+        assert!(violations(1.0, (1.0, 1.0), (3.5, 1.0), 0.5).is_empty());
+        assert_eq!(
+            violations(1.0, (0.5, 1.0), (4.0, 1.0), 0.0),
+            [Violation::Spacing { a: 0, b: None }]
+        );
+    }
+
+    #[test]
+    fn test_validate_grain() {
+        let grain = |allow_reverse| Grain::new(Point2D { x: 1.0, y: 0.0 }, 0.1, allow_reverse);
+        let problem = IrregularBinPackingProblem::builder()
+            .bin_descriptions([BinDescription::new(rectangle(10.0, 10.0), None, 1.0)
+                .with_grain(Point2D { x: 0.0, y: 1.0 })])
+            .piece_descriptions([
+                PieceDescription::new(
+                    rectangle(4.0, 1.0),
+                    RotationPolicy::Continuous(FRAC_PI_2),
+                    1,
+                )
+                .with_grain(grain(false)),
+                PieceDescription::new(
+                    rectangle(4.0, 1.0),
+                    RotationPolicy::Continuous(FRAC_PI_2),
+                    1,
+                )
+                .with_grain(grain(true)),
+            ])
+            .build()
+            .unwrap();
+        let rejected = |piece_id, rotation, mirrored| {
+            let mut placement = IrregularBinPackingPlacement::new(
+                0,
+                piece_id,
+                Point2D { x: 0.0, y: 0.0 },
+                rotation,
+            );
+            placement.set_mirrored(mirrored);
+            let solution = IrregularBinPackingSolution::new(vec![placement]);
+            validate(&problem, &solution, 0.0).contains(&Violation::Grain { placement: 0 })
+        };
+
+        assert!(!rejected(0, FRAC_PI_2, false));
+        assert!(!rejected(0, FRAC_PI_2 + 0.05, false));
+        assert!(rejected(0, FRAC_PI_2 + 0.2, false));
+        assert!(rejected(0, 0.0, false));
+        assert!(rejected(0, 3.0 * FRAC_PI_2, false));
+        assert!(!rejected(1, 3.0 * FRAC_PI_2, false));
+        // flipped over the grain points the other way
+        assert!(rejected(0, FRAC_PI_2, true));
+        assert!(!rejected(0, 3.0 * FRAC_PI_2, true));
+
+        let aligned =
+            problem.piece_descriptions()[1].rotation_candidates(Point2D { x: 0.0, y: 1.0 }, false);
+        assert_eq!(aligned.len(), 2);
+        approx::assert_abs_diff_eq!(aligned[..], [FRAC_PI_2, FRAC_PI_2 + PI][..], epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,335 @@
+//! A cache of no fit polygons that can be saved to and loaded from disk.
+//!
+//! No fit polygons are keyed by a hash of the geometry of both polygons
+//! rather than by their position in a problem, so the same part library
+//! can reuse its no fit polygons across runs and across problems.
+//! The geometry is normalized by moving the first vertex to the origin,
+//! so polygons that only differ by a translation share their no fit polygons.
+//!
+//! The file format is a little endian binary format:
+//! - the magic bytes `BNFP` followed by the format version as a u32
+//! - the number of entries as a u64
+//! - for each entry: the hashes of both polygons as u64, both rotations as f64,
+//!   the inside and search edges flags as u8, the number of contours as a u32
+//!   and for each contour the number of points as a u32 followed by x y pairs as f64.
+//!
+//! Contours are stored relative to the first vertex of the stationary polygon.
+use crate::no_fit_polygon::ComputeNoFitPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::{NumCast, ToPrimitive, Zero};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"BNFP";
+const VERSION: u32 = 1;
+
+/// Identifies a no fit polygon independently of where the polygons are placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NFPCacheKey {
+    a: u64,
+    b: u64,
+    a_rotation: u64,
+    b_rotation: u64,
+    inside: bool,
+    search_edges: bool,
+}
+
+impl NFPCacheKey {
+    /// Creates the key of the no fit polygon of `b` around (or inside of) `a`.
+    pub fn new<P: Polygon>(a: &P, b: &P, inside: bool, search_edges: bool) -> Self {
+        Self {
+            a: geometry_hash(a),
+            b: geometry_hash(b),
+            a_rotation: value_bits(a.rotation()),
+            b_rotation: value_bits(b.rotation()),
+            inside,
+            search_edges,
+        }
+    }
+}
+
+/// Returns a hash of the local vertices of the polygon after moving the
+/// first vertex to the origin, and of whether it is mirrored.
+/// The hash is stable across runs and platforms.
+pub fn geometry_hash<P: Polygon>(polygon: &P) -> u64 {
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut write = |bits: u64| {
+        for byte in bits.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    write(polygon.length() as u64);
+    let first = polygon
+        .iter_vertices_local()
+        .next()
+        .copied()
+        .unwrap_or(Zero::zero());
+    for vertex in polygon.iter_vertices_local() {
+        write(value_bits(vertex.x() - first.x()));
+        write(value_bits(vertex.y() - first.y()));
+    }
+    // only mirrored polygons hash the flag, so existing cache files stay valid
+    if polygon.mirrored() {
+        write(1);
+    }
+    hash
+}
+
+/// Returns the bits of a value with negative zero folded into zero.
+fn value_bits<T: ToPrimitive>(value: T) -> u64 {
+    let value = value.to_f64().unwrap();
+    if value == 0.0 {
+        0.0f64.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NFPCache<P: Polygon> {
+    entries: HashMap<NFPCacheKey, Vec<Vec<P::Point>>>,
+}
+
+impl<P: Polygon> Default for NFPCache<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Polygon> NFPCache<P> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of cached no fit polygons
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the cached no fit polygon of `b` around (or inside of) `a`
+    /// in the same coordinates `ComputeNoFitPolygon::no_fit_polygon` would return.
+    pub fn get(&self, a: &P, b: &P, inside: bool, search_edges: bool) -> Option<Vec<Vec<P::Point>>>
+    where
+        P: ComputeNoFitPolygon,
+    {
+        let nfp = self
+            .entries
+            .get(&NFPCacheKey::new(a, b, inside, search_edges))?;
+        let reference = a.get_vertex(0);
+        Some(
+            nfp.iter()
+                .map(|contour| contour.iter().map(|point| *point + reference).collect())
+                .collect(),
+        )
+    }
+
+    /// Stores the no fit polygon of `b` around (or inside of) `a`.
+    pub fn insert(&mut self, a: &P, b: &P, inside: bool, search_edges: bool, nfp: &[Vec<P::Point>])
+    where
+        P: ComputeNoFitPolygon,
+    {
+        let reference = a.get_vertex(0);
+        self.entries.insert(
+            NFPCacheKey::new(a, b, inside, search_edges),
+            nfp.iter()
+                .map(|contour| contour.iter().map(|point| *point - reference).collect())
+                .collect(),
+        );
+    }
+
+    /// Adds all entries of `other`, replacing entries with the same key.
+    pub fn extend(&mut self, other: NFPCache<P>) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Writes the cache in the versioned binary format described in the module documentation.
+    pub fn save<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for (key, nfp) in self.entries.iter() {
+            writer.write_all(&key.a.to_le_bytes())?;
+            writer.write_all(&key.b.to_le_bytes())?;
+            writer.write_all(&key.a_rotation.to_le_bytes())?;
+            writer.write_all(&key.b_rotation.to_le_bytes())?;
+            writer.write_all(&[key.inside as u8, key.search_edges as u8])?;
+            writer.write_all(&(nfp.len() as u32).to_le_bytes())?;
+            for contour in nfp.iter() {
+                writer.write_all(&(contour.len() as u32).to_le_bytes())?;
+                for point in contour.iter() {
+                    writer.write_all(&point.x().to_f64().unwrap().to_le_bytes())?;
+                    writer.write_all(&point.y().to_f64().unwrap().to_le_bytes())?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a cache written by `save`.
+    pub fn load<R: Read>(reader: R) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(anyhow!("Not an nfp cache file"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(anyhow!("Unsupported nfp cache version {}", version));
+        }
+
+        let n_entries = read_u64(&mut reader)?;
+        let mut entries = HashMap::new();
+        for _ in 0..n_entries {
+            let a = read_u64(&mut reader)?;
+            let b = read_u64(&mut reader)?;
+            let a_rotation = read_u64(&mut reader)?;
+            let b_rotation = read_u64(&mut reader)?;
+            let mut flags = [0u8; 2];
+            reader.read_exact(&mut flags)?;
+            let key = NFPCacheKey {
+                a,
+                b,
+                a_rotation,
+                b_rotation,
+                inside: flags[0] != 0,
+                search_edges: flags[1] != 0,
+            };
+
+            // the counts are not trusted for allocating, a corrupt file could claim anything
+            let n_contours = read_u32(&mut reader)?;
+            let mut nfp = vec![];
+            for _ in 0..n_contours {
+                let n_points = read_u32(&mut reader)?;
+                let mut contour = vec![];
+                for _ in 0..n_points {
+                    let x = f64::from_bits(read_u64(&mut reader)?);
+                    let y = f64::from_bits(read_u64(&mut reader)?);
+                    contour.push(P::Point::from_xy(
+                        <<P::Point as Point2D>::Value as NumCast>::from(x)
+                            .ok_or(anyhow!("Invalid coordinate"))?,
+                        <<P::Point as Point2D>::Value as NumCast>::from(y)
+                            .ok_or(anyhow!("Invalid coordinate"))?,
+                    ));
+                }
+                nfp.push(contour);
+            }
+            entries.insert(key, nfp);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Saves the cache to a file, overwriting it if it exists.
+    pub fn save_to_file<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        self.save(File::create(path)?)
+    }
+
+    /// Loads a cache from a file written by `save_to_file`.
+    pub fn load_from_file<T: AsRef<Path>>(path: T) -> Result<Self> {
+        Self::load(File::open(path)?)
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::{Point2D, Polygon};
+    use crate::polygon::Polygon as _;
+
+    #[test]
+    fn test_translated_polygons_share_entries() {
+        let a = Polygon::from_tuples([(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]);
+        let b = Polygon::from_tuples([(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)]);
+        let nfp = a.no_fit_polygon(&b, false, false).unwrap();
+
+        let mut cache = NFPCache::new();
+        cache.insert(&a, &b, false, false, &nfp);
+
+        let mut moved_a =
+            Polygon::from_tuples([(10.0, 10.0), (15.0, 10.0), (15.0, 15.0), (10.0, 15.0)]);
+        moved_a.set_offset(Point2D { x: 1.0, y: 2.0 });
+        let moved_b = Polygon::from_tuples([(3.0, 3.0), (5.0, 3.0), (4.0, 5.0)]);
+        let cached = cache.get(&moved_a, &moved_b, false, false).unwrap();
+        let expected = moved_a.no_fit_polygon(&moved_b, false, false).unwrap();
+        for (i, j) in cached.iter().flatten().zip(expected.iter().flatten()) {
+            approx::assert_abs_diff_eq!(i, j, epsilon = 1e-9);
+        }
+
+        assert!(cache.get(&a, &b, true, false).is_none());
+        let mut rotated_b = b.clone();
+        rotated_b.set_rotation(1.0);
+        assert!(cache.get(&a, &rotated_b, false, false).is_none());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let a = Polygon::from_tuples([(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]);
+        let mut b = Polygon::from_tuples([(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)]);
+        b.set_rotation(0.5);
+        let nfp = a.no_fit_polygon(&b, false, false).unwrap();
+        let mut cache = NFPCache::new();
+        cache.insert(&a, &b, false, false, &nfp);
+
+        let mut bytes = vec![];
+        cache.save(&mut bytes).unwrap();
+        let loaded = NFPCache::<Polygon>::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get(&a, &b, false, false), Some(nfp));
+
+        bytes[4] = 99;
+        assert!(NFPCache::<Polygon>::load(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_load_truncated_or_corrupt() {
+        let a = Polygon::from_tuples([(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]);
+        let b = Polygon::from_tuples([(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)]);
+        let nfp = a.no_fit_polygon(&b, false, false).unwrap();
+        let mut cache = NFPCache::new();
+        cache.insert(&a, &b, false, false, &nfp);
+        let mut bytes = vec![];
+        cache.save(&mut bytes).unwrap();
+
+        for length in [0, 3, 8, 16, 40, bytes.len() - 1] {
+            assert!(NFPCache::<Polygon>::load(&bytes[..length]).is_err());
+        }
+
+        // huge counts of entries, contours and points with no data behind them
+        let mut garbage = MAGIC.to_vec();
+        garbage.extend(VERSION.to_le_bytes());
+        garbage.extend(u64::MAX.to_le_bytes());
+        assert!(NFPCache::<Polygon>::load(garbage.as_slice()).is_err());
+        garbage.extend([0u8; 34]);
+        garbage.extend(u32::MAX.to_le_bytes());
+        garbage.extend(u32::MAX.to_le_bytes());
+        assert!(NFPCache::<Polygon>::load(garbage.as_slice()).is_err());
+    }
+}
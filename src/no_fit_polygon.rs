@@ -612,6 +612,42 @@ pub trait ComputeNoFitPolygon: Polygon {
     }
 }
 
+/// Returns the inner fit polygon of `piece` inside of `bin` if `bin` is an
+/// axis aligned rectangle. The contour is empty if `piece` does not fit.
+/// Returns None if `bin` is not an axis aligned rectangle.
+pub fn rectangle_inner_fit_polygon<P: Polygon>(bin: &P, piece: &P) -> Option<Vec<Vec<P::Point>>> {
+    let bin_bbox = bin.bounding_box();
+    if bin.length() != 4
+        || !abs_diff_eq!(
+            bin.area().abs(),
+            bin_bbox.width() * bin_bbox.height(),
+            epsilon = P::Point::value_epsilon()
+        )
+    {
+        return None;
+    }
+
+    let piece_bbox = piece.bounding_box();
+    if piece_bbox.width() > bin_bbox.width() + P::Point::epsilon()
+        || piece_bbox.height() > bin_bbox.height() + P::Point::epsilon()
+    {
+        return Some(vec![]);
+    }
+
+    // the contour tracks the first vertex of the piece
+    let reference = piece.iter_vertices().next()?;
+    let min_x = bin_bbox.min_x - piece_bbox.min_x + reference.x();
+    let min_y = bin_bbox.min_y - piece_bbox.min_y + reference.y();
+    let max_x = bin_bbox.max_x - piece_bbox.max_x + reference.x();
+    let max_y = bin_bbox.max_y - piece_bbox.max_y + reference.y();
+    Some(vec![vec![
+        P::Point::from_xy(min_x, min_y),
+        P::Point::from_xy(max_x.max(min_x), min_y),
+        P::Point::from_xy(max_x.max(min_x), max_y.max(min_y)),
+        P::Point::from_xy(min_x, max_y.max(min_y)),
+    ]])
+}
+
 mod tests {
     #[test]
     fn test_no_fit_polygon_one_convex_no_holes_outside() {
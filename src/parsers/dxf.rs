@@ -0,0 +1,714 @@
+//! Parser for closed contours in DXF drawings
+//!
+//! Reads the LWPOLYLINE, POLYLINE, LINE, ARC, CIRCLE and SPLINE entities of the ENTITIES section.
+//! Lines, arcs and open polylines or splines are stitched into closed loops where their
+//! end points meet, chains that can't be closed are ignored.
+//! Loops inside of other loops become holes, loops inside of holes become separate pieces.
+//!
+//! Arcs keep their exact shape until flattened, splines are flattened while parsing.
+//! Only 2D geometry is supported, elevations are ignored. Arcs, circles and 2D polylines
+//! drawn with the extrusion direction pointing down the z axis are mirrored into place.
+
+use crate::curve::{
+    nest_curved_contours, CircularArc, CubicBezier, Curve, CurveSegment, CurvedContour,
+    CurvedMultiPolygon, Line, QuadraticBezier,
+};
+use crate::multi_polygon::MultiPolygon;
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::{FloatConst, NumCast, ToPrimitive};
+use std::io::{BufRead, BufReader, Read};
+
+/// Parses the closed contours of a DXF drawing into pieces.
+/// Curves are flattened so they deviate at most `tolerance` from the drawing,
+/// end points closer than `tolerance` are considered the same point.
+pub fn parse_dxf<P, R>(
+    reader: R,
+    tolerance: <P::Point as Point2D>::Value,
+) -> Result<Vec<MultiPolygon<P>>>
+where
+    P: Polygon + From<Vec<P::Point>>,
+    <P::Point as Point2D>::Value: FloatConst,
+    R: Read,
+{
+    Ok(parse_dxf_curves(reader, tolerance)?
+        .iter()
+        .map(|piece| piece.to_multi_polygon(tolerance))
+        .collect())
+}
+
+/// Parses the closed contours of a DXF drawing into pieces keeping their arcs,
+/// eg. to export placed pieces with their original curves.
+/// Splines are flattened so they deviate at most `tolerance` from the drawing,
+/// end points closer than `tolerance` are considered the same point.
+pub fn parse_dxf_curves<T, R>(reader: R, tolerance: T::Value) -> Result<Vec<CurvedMultiPolygon<T>>>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+    R: Read,
+{
+    let pairs = read_pairs(reader)?;
+    let mut closed = vec![];
+    let mut open = vec![];
+    for entity in entities(&pairs) {
+        let Some((segments, is_closed)) = entity_segments::<T>(&entity, tolerance)? else {
+            continue;
+        };
+        if segments.is_empty() {
+            continue;
+        }
+        if is_closed {
+            closed.push(CurvedContour::new(segments));
+        } else {
+            open.push(segments);
+        }
+    }
+    closed.extend(stitch(open, tolerance));
+    Ok(nest_curved_contours(closed, tolerance))
+}
+
+/// Returns the group code and value pairs of the file.
+fn read_pairs<R: Read>(reader: R) -> Result<Vec<(i32, String)>> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut pairs = vec![];
+    while let Some(code) = lines.next() {
+        let code = code?;
+        if code.trim().is_empty() {
+            continue;
+        }
+        let code: i32 = code
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Invalid group code {:?}", code.trim()))?;
+        let value = lines
+            .next()
+            .ok_or(anyhow!("Missing value for group code {}", code))??;
+        pairs.push((code, value.trim().to_string()));
+    }
+    Ok(pairs)
+}
+
+/// An entity with its group codes, for POLYLINE entities
+/// the VERTEX entities following it are its children.
+struct Entity<'a> {
+    kind: &'a str,
+    pairs: &'a [(i32, String)],
+    children: Vec<&'a [(i32, String)]>,
+}
+
+impl Entity<'_> {
+    fn values(&self, code: i32) -> impl Iterator<Item = Result<f64>> + '_ {
+        values(self.pairs, code)
+    }
+
+    fn value(&self, code: i32) -> Result<Option<f64>> {
+        self.values(code).next().transpose()
+    }
+
+    fn required(&self, code: i32) -> Result<f64> {
+        self.value(code)?.ok_or(anyhow!(
+            "{} entity is missing group code {}",
+            self.kind,
+            code
+        ))
+    }
+
+    fn flags(&self) -> Result<i64> {
+        Ok(self.value(70)?.unwrap_or(0.0) as i64)
+    }
+
+    /// True if the entity is drawn in its own coordinate system (OCS) with a flipped z axis,
+    /// which mirrors x. Lines, splines and 3D polylines are in world coordinates whatever
+    /// their extrusion direction.
+    fn mirrored(&self) -> Result<bool> {
+        let in_ocs = match self.kind {
+            "ARC" | "CIRCLE" | "LWPOLYLINE" => true,
+            // 3D polylines and polygon or polyface meshes
+            "POLYLINE" => self.flags()? & (8 | 16 | 64) == 0,
+            _ => false,
+        };
+        Ok(in_ocs && self.value(230)?.unwrap_or(1.0) < 0.0)
+    }
+}
+
+fn values(pairs: &[(i32, String)], code: i32) -> impl Iterator<Item = Result<f64>> + '_ {
+    pairs
+        .iter()
+        .filter(move |(c, _)| *c == code)
+        .map(|(c, value)| {
+            value
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid number {:?} for group code {}", value, c))
+        })
+}
+
+/// Returns the entities of the ENTITIES section.
+fn entities(pairs: &[(i32, String)]) -> Vec<Entity<'_>> {
+    let mut in_entities = false;
+    let mut starts = vec![];
+    for (i, (code, value)) in pairs.iter().enumerate() {
+        if *code != 0 {
+            if *code == 2 && value == "ENTITIES" && i > 0 && pairs[i - 1] == (0, "SECTION".into()) {
+                in_entities = true;
+            }
+            continue;
+        }
+        if value == "ENDSEC" {
+            if in_entities {
+                starts.push(i);
+            }
+            in_entities = false;
+        } else if in_entities {
+            starts.push(i);
+        }
+    }
+
+    let mut entities: Vec<Entity> = vec![];
+    for (start, end) in starts.iter().zip(starts.iter().skip(1)) {
+        let kind = pairs[*start].1.as_str();
+        let entity_pairs = &pairs[start + 1..*end];
+        match kind {
+            "VERTEX" => {
+                if let Some(polyline) = entities.last_mut().filter(|e| e.kind == "POLYLINE") {
+                    polyline.children.push(entity_pairs);
+                }
+            }
+            "SEQEND" => {}
+            _ => entities.push(Entity {
+                kind,
+                pairs: entity_pairs,
+                children: vec![],
+            }),
+        }
+    }
+    entities
+}
+
+fn point<T: Point2D>(x: f64, y: f64) -> T {
+    T::from_xy(
+        <T::Value as NumCast>::from(x).unwrap(),
+        <T::Value as NumCast>::from(y).unwrap(),
+    )
+}
+
+/// Returns the segments of the entity and whether they form a closed loop,
+/// or None if the entity is not a supported contour.
+fn entity_segments<T>(
+    entity: &Entity,
+    tolerance: T::Value,
+) -> Result<Option<(Vec<CurveSegment<T>>, bool)>>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let (segments, closed) = match entity.kind {
+        "LINE" => {
+            let start = point(entity.required(10)?, entity.required(20)?);
+            let end = point(entity.required(11)?, entity.required(21)?);
+            (vec![Line::new(start, end).into()], false)
+        }
+        "ARC" => {
+            let center = point(entity.required(10)?, entity.required(20)?);
+            let radius = entity.required(40)?;
+            let start_angle = entity.required(50)?;
+            let mut sweep = entity.required(51)? - start_angle;
+            // arcs are counter-clockwise from the start to the end angle
+            if sweep <= 0.0 {
+                sweep += 360.0;
+            }
+            let arc = CircularArc::new(
+                center,
+                <T::Value as NumCast>::from(radius).unwrap(),
+                <T::Value as NumCast>::from(start_angle.to_radians()).unwrap(),
+                <T::Value as NumCast>::from(sweep.to_radians()).unwrap(),
+            );
+            (vec![arc.into()], false)
+        }
+        "CIRCLE" => {
+            let center = point(entity.required(10)?, entity.required(20)?);
+            let radius = <T::Value as NumCast>::from(entity.required(40)?).unwrap();
+            (vec![CircularArc::circle(center, radius).into()], true)
+        }
+        "LWPOLYLINE" => {
+            // a bulge belongs to the segment starting at the vertex before it
+            let mut vertices: Vec<(f64, f64, f64)> = vec![];
+            let mut x = None;
+            for (code, value) in entity.pairs.iter() {
+                let parse = || {
+                    value
+                        .parse::<f64>()
+                        .map_err(|_| anyhow!("Invalid number {:?} for group code {}", value, code))
+                };
+                match code {
+                    10 => x = Some(parse()?),
+                    20 => {
+                        let x = x.take().ok_or(anyhow!("LWPOLYLINE vertex is missing x"))?;
+                        vertices.push((x, parse()?, 0.0));
+                    }
+                    42 => {
+                        if let Some(vertex) = vertices.last_mut() {
+                            vertex.2 = parse()?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            polyline_segments(&vertices, entity.flags()? & 1 == 1)
+        }
+        "POLYLINE" => {
+            let mut vertices = vec![];
+            for child in entity.children.iter() {
+                let flags = values(child, 70).next().transpose()?.unwrap_or(0.0) as i64;
+                // spline frame control points are not on the curve
+                if flags & 16 != 0 {
+                    continue;
+                }
+                let x = values(child, 10).next().transpose()?;
+                let y = values(child, 20).next().transpose()?;
+                let (Some(x), Some(y)) = (x, y) else {
+                    return Err(anyhow!("POLYLINE vertex is missing coordinates"));
+                };
+                let bulge = values(child, 42).next().transpose()?.unwrap_or(0.0);
+                vertices.push((x, y, bulge));
+            }
+            polyline_segments(&vertices, entity.flags()? & 1 == 1)
+        }
+        "SPLINE" => {
+            let points = spline_points(entity, tolerance.to_f64().unwrap())?;
+            let segments = points
+                .windows(2)
+                .map(|w| Line::new(point(w[0].0, w[0].1), point(w[1].0, w[1].1)).into())
+                .collect();
+            (segments, false)
+        }
+        _ => return Ok(None),
+    };
+
+    if entity.mirrored()? {
+        return Ok(Some((segments.iter().map(mirror).collect(), closed)));
+    }
+    Ok(Some((segments, closed)))
+}
+
+/// Returns the segments through the vertices, arcs where the bulge is not zero.
+fn polyline_segments<T>(vertices: &[(f64, f64, f64)], closed: bool) -> (Vec<CurveSegment<T>>, bool)
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let n = vertices.len();
+    let count = if closed { n } else { n.saturating_sub(1) };
+    let segments = (0..count)
+        .filter_map(|i| {
+            let (x0, y0, bulge) = vertices[i];
+            let (x1, y1, _) = vertices[(i + 1) % n];
+            let (start, end): (T, T) = (point(x0, y0), point(x1, y1));
+            if x0 == x1 && y0 == y1 {
+                return None;
+            }
+            Some(
+                match CircularArc::from_bulge(
+                    start,
+                    end,
+                    <T::Value as NumCast>::from(bulge).unwrap(),
+                ) {
+                    Some(arc) => arc.into(),
+                    None => Line::new(start, end).into(),
+                },
+            )
+        })
+        .collect();
+    (segments, closed)
+}
+
+/// Mirrors the segment along the y axis.
+fn mirror<T>(segment: &CurveSegment<T>) -> CurveSegment<T>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let flip = |p: T| T::from_xy(-p.x(), p.y());
+    match segment {
+        CurveSegment::Line(line) => Line::new(flip(line.start), flip(line.end)).into(),
+        CurveSegment::Arc(arc) => CircularArc::new(
+            flip(arc.center),
+            arc.radius,
+            T::Value::PI() - arc.start_angle,
+            -arc.sweep,
+        )
+        .into(),
+        CurveSegment::Quadratic(bezier) => {
+            QuadraticBezier::new(flip(bezier.start), flip(bezier.control), flip(bezier.end)).into()
+        }
+        CurveSegment::Cubic(bezier) => CubicBezier::new(
+            flip(bezier.start),
+            flip(bezier.control1),
+            flip(bezier.control2),
+            flip(bezier.end),
+        )
+        .into(),
+    }
+}
+
+/// Returns points along the spline no further than `tolerance` from it.
+/// Splines given only by fit points are approximated by the polyline through them.
+fn spline_points(entity: &Entity, tolerance: f64) -> Result<Vec<(f64, f64)>> {
+    let degree = entity.value(71)?.unwrap_or(3.0) as usize;
+    let knots = entity.values(40).collect::<Result<Vec<_>>>()?;
+    let xs = entity.values(10).collect::<Result<Vec<_>>>()?;
+    let ys = entity.values(20).collect::<Result<Vec<_>>>()?;
+    let control: Vec<(f64, f64)> = xs.into_iter().zip(ys).collect();
+    let mut weights = entity.values(41).collect::<Result<Vec<_>>>()?;
+
+    if control.is_empty() || knots.len() != control.len() + degree + 1 {
+        let xs = entity.values(11).collect::<Result<Vec<_>>>()?;
+        let ys = entity.values(21).collect::<Result<Vec<_>>>()?;
+        if xs.is_empty() {
+            return Err(anyhow!(
+                "SPLINE has neither valid control points nor fit points"
+            ));
+        }
+        return Ok(xs.into_iter().zip(ys).collect());
+    }
+    if weights.len() != control.len() {
+        weights = vec![1.0; control.len()];
+    }
+
+    let spline = BSpline {
+        degree,
+        knots,
+        control,
+        weights,
+    };
+    let mut points = vec![spline.evaluate(spline.knots[degree])];
+    for span in degree..spline.control.len() {
+        let (t0, t1) = (spline.knots[span], spline.knots[span + 1]);
+        if t1 <= t0 {
+            continue;
+        }
+        // split every span a few times so curves crossing their chord are not mistaken for lines
+        let pieces = 4;
+        for i in 0..pieces {
+            let a = t0 + (t1 - t0) * i as f64 / pieces as f64;
+            let b = t0 + (t1 - t0) * (i + 1) as f64 / pieces as f64;
+            spline.subdivide(a, b, tolerance, 16, &mut points);
+        }
+    }
+    Ok(points)
+}
+
+struct BSpline {
+    degree: usize,
+    knots: Vec<f64>,
+    control: Vec<(f64, f64)>,
+    weights: Vec<f64>,
+}
+
+impl BSpline {
+    /// Evaluates the (rational) spline at `t` using de Boor's algorithm.
+    fn evaluate(&self, t: f64) -> (f64, f64) {
+        let p = self.degree;
+        let n = self.control.len();
+        // the span containing t, clamped to the valid range
+        let mut k = p;
+        while k + 1 < n && self.knots[k + 1] <= t {
+            k += 1;
+        }
+        let mut d: Vec<(f64, f64, f64)> = (0..=p)
+            .map(|j| {
+                let (x, y) = self.control[j + k - p];
+                let w = self.weights[j + k - p];
+                (x * w, y * w, w)
+            })
+            .collect();
+        for r in 1..=p {
+            for j in (r..=p).rev() {
+                let left = self.knots[j + k - p];
+                let right = self.knots[j + 1 + k - r];
+                let alpha = if right > left {
+                    (t - left) / (right - left)
+                } else {
+                    0.0
+                };
+                d[j] = (
+                    (1.0 - alpha) * d[j - 1].0 + alpha * d[j].0,
+                    (1.0 - alpha) * d[j - 1].1 + alpha * d[j].1,
+                    (1.0 - alpha) * d[j - 1].2 + alpha * d[j].2,
+                );
+            }
+        }
+        (d[p].0 / d[p].2, d[p].1 / d[p].2)
+    }
+
+    /// Appends points of the spline from `a` (excluded) to `b` (included),
+    /// halving the interval while its middle is further than `tolerance` from the chord.
+    fn subdivide(
+        &self,
+        a: f64,
+        b: f64,
+        tolerance: f64,
+        depth: usize,
+        points: &mut Vec<(f64, f64)>,
+    ) {
+        let start = self.evaluate(a);
+        let end = self.evaluate(b);
+        let middle = (a + b) / 2.0;
+        let point = self.evaluate(middle);
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let distance = if length > 0.0 {
+            ((point.0 - start.0) * dy - (point.1 - start.1) * dx).abs() / length
+        } else {
+            ((point.0 - start.0).powi(2) + (point.1 - start.1).powi(2)).sqrt()
+        };
+        if depth > 0 && distance > tolerance {
+            self.subdivide(a, middle, tolerance, depth - 1, points);
+            self.subdivide(middle, b, tolerance, depth - 1, points);
+        } else {
+            points.push(end);
+        }
+    }
+}
+
+fn close<T: Point2D>(a: T, b: T, tolerance: T::Value) -> bool {
+    let d = a - b;
+    d.dot(&d) <= tolerance * tolerance
+}
+
+/// Joins chains of segments meeting end to end into closed loops.
+fn stitch<T>(mut chains: Vec<Vec<CurveSegment<T>>>, tolerance: T::Value) -> Vec<CurvedContour<T>>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let mut loops = vec![];
+    while let Some(mut chain) = chains.pop() {
+        loop {
+            let start = chain[0].start();
+            let end = chain[chain.len() - 1].end();
+            if close(start, end, tolerance) {
+                loops.push(CurvedContour::new(chain));
+                break;
+            }
+            let next = chains.iter().enumerate().find_map(|(i, other)| {
+                if close(end, other[0].start(), tolerance) {
+                    Some((i, false))
+                } else if close(end, other[other.len() - 1].end(), tolerance) {
+                    Some((i, true))
+                } else {
+                    None
+                }
+            });
+            let Some((i, reverse)) = next else {
+                // the chain can't be closed
+                break;
+            };
+            let other = chains.swap_remove(i);
+            if reverse {
+                chain.extend(other.iter().rev().map(|segment| segment.reversed()));
+            } else {
+                chain.extend(other);
+            }
+        }
+    }
+    loops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::Polygon;
+    use crate::polygon::Polygon as _;
+    use std::f64::consts::PI;
+
+    /// Returns a DXF file with the given entities, each a list of group codes and values.
+    fn dxf(entities: &[&[(i32, &str)]]) -> String {
+        let mut pairs = vec![(0, "SECTION"), (2, "HEADER"), (0, "ENDSEC")];
+        pairs.extend([(0, "SECTION"), (2, "ENTITIES")]);
+        for entity in entities {
+            pairs.extend(entity.iter().copied());
+        }
+        pairs.extend([(0, "ENDSEC"), (0, "EOF")]);
+        pairs
+            .iter()
+            .map(|(code, value)| format!("{}\n{}\n", code, value))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_polyline_with_circle_hole() {
+        let file = dxf(&[
+            &[
+                (0, "LWPOLYLINE"),
+                (8, "0"),
+                (90, "4"),
+                (70, "1"),
+                (10, "0"),
+                (20, "0"),
+                (10, "10"),
+                (20, "0"),
+                (10, "10"),
+                (20, "10"),
+                (10, "0"),
+                (20, "10"),
+            ],
+            &[(0, "CIRCLE"), (10, "5"), (20, "5"), (40, "2")],
+        ]);
+        let pieces = parse_dxf::<Polygon, _>(file.as_bytes(), 0.001).unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].holes().len(), 1);
+        approx::assert_abs_diff_eq!(pieces[0].area(), 100.0 - 4.0 * PI, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_mirror_only_entities_in_their_own_coordinate_system() {
+        // a 2 by 2 square with a half disk on its left, the arc drawn with a flipped z axis,
+        // one of the lines too but lines are in world coordinates
+        let file = dxf(&[
+            &[
+                (0, "ARC"),
+                (10, "0"),
+                (20, "1"),
+                (40, "1"),
+                (50, "270"),
+                (51, "90"),
+                (210, "0"),
+                (220, "0"),
+                (230, "-1"),
+            ],
+            &[(0, "LINE"), (10, "0"), (20, "0"), (11, "2"), (21, "0")],
+            &[
+                (0, "LINE"),
+                (10, "2"),
+                (20, "0"),
+                (11, "2"),
+                (21, "2"),
+                (210, "0"),
+                (220, "0"),
+                (230, "-1"),
+            ],
+            &[(0, "LINE"), (10, "2"), (20, "2"), (11, "0"), (21, "2")],
+        ]);
+        let pieces = parse_dxf::<Polygon, _>(file.as_bytes(), 1e-3).unwrap();
+        assert_eq!(pieces.len(), 1);
+        let bounding_box = pieces[0].bounding_box();
+        approx::assert_abs_diff_eq!(bounding_box.min_x, -1.0, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(bounding_box.max_x, 2.0);
+        approx::assert_abs_diff_eq!(pieces[0].area(), 4.0 + PI / 2.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_stitch_lines_and_arcs() {
+        // a slot drawn as separate lines and arcs, one line reversed,
+        // and an island inside of a hole which is a piece of its own
+        let file = dxf(&[
+            &[(0, "LINE"), (10, "0"), (20, "-1"), (11, "4"), (21, "-1")],
+            &[(0, "LINE"), (10, "0"), (20, "1"), (11, "4"), (21, "1")],
+            &[
+                (0, "ARC"),
+                (10, "4"),
+                (20, "0"),
+                (40, "1"),
+                (50, "270"),
+                (51, "90"),
+            ],
+            &[
+                (0, "ARC"),
+                (10, "0"),
+                (20, "0"),
+                (40, "1"),
+                (50, "90"),
+                (51, "270"),
+            ],
+            &[(0, "LINE"), (10, "20"), (20, "20"), (11, "30"), (21, "20")],
+            &[
+                (0, "POLYLINE"),
+                (70, "1"),
+                (0, "VERTEX"),
+                (10, "-10"),
+                (20, "-10"),
+                (0, "VERTEX"),
+                (10, "20"),
+                (20, "-10"),
+                (0, "VERTEX"),
+                (10, "20"),
+                (20, "10"),
+                (0, "VERTEX"),
+                (10, "-10"),
+                (20, "10"),
+                (0, "SEQEND"),
+            ],
+            &[(0, "CIRCLE"), (10, "0"), (20, "0"), (40, "5")],
+        ]);
+        let pieces =
+            parse_dxf_curves::<crate::kernelf64::Point2D, _>(file.as_bytes(), 1e-6).unwrap();
+        assert_eq!(pieces.len(), 2);
+        let frame = pieces.iter().find(|p| !p.holes.is_empty()).unwrap();
+        assert_eq!(frame.outer.segments.len(), 4);
+        let slot = pieces.iter().find(|p| p.holes.is_empty()).unwrap();
+        assert_eq!(slot.outer.segments.len(), 4);
+        let polygon: Polygon = slot.outer.to_polygon(0.001);
+        approx::assert_abs_diff_eq!(polygon.area().abs(), 8.0 + PI, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_parse_bulge_and_spline() {
+        let file = dxf(&[
+            // a 2 by 2 square with a half circle bulging out of its right side
+            &[
+                (0, "LWPOLYLINE"),
+                (70, "1"),
+                (10, "0"),
+                (20, "0"),
+                (10, "2"),
+                (20, "0"),
+                (42, "1"),
+                (10, "2"),
+                (20, "2"),
+                (10, "0"),
+                (20, "2"),
+            ],
+            // a closed quadratic B-spline, a rounded triangle
+            &[
+                (0, "SPLINE"),
+                (70, "1"),
+                (71, "2"),
+                (72, "8"),
+                (73, "5"),
+                (40, "0"),
+                (40, "0"),
+                (40, "0"),
+                (40, "1"),
+                (40, "2"),
+                (40, "3"),
+                (40, "3"),
+                (40, "3"),
+                (10, "10"),
+                (20, "0"),
+                (10, "20"),
+                (20, "0"),
+                (10, "15"),
+                (20, "10"),
+                (10, "10"),
+                (20, "10"),
+                (10, "10"),
+                (20, "0"),
+            ],
+        ]);
+        let pieces = parse_dxf::<Polygon, _>(file.as_bytes(), 0.001).unwrap();
+        assert_eq!(pieces.len(), 2);
+        let rounded = pieces
+            .iter()
+            .find(|p| p.bounding_box().max_x < 5.0)
+            .unwrap();
+        approx::assert_abs_diff_eq!(rounded.area(), 4.0 + PI / 2.0, epsilon = 0.01);
+        approx::assert_abs_diff_eq!(rounded.bounding_box().max_x, 3.0, epsilon = 0.001);
+        let spline = pieces
+            .iter()
+            .find(|p| p.bounding_box().min_x >= 10.0 - 1e-9)
+            .unwrap();
+        // the curve passes through the clamped end points and stays inside of its control polygon
+        assert!(spline.area() > 0.0 && spline.area() < 75.0);
+    }
+}
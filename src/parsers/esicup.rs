@@ -0,0 +1,347 @@
+//! Parser for the ESICUP nesting datasets
+//!
+//! Reads the XML format the datasets of the EURO Special Interest Group on Cutting and Packing
+//! are published in, eg. Albano, Dagli, Jakobs, Shapes, Swim, Trousers, Fu, Mao, Marques and Shirts.
+//! An instance lists the boards in `<problem><boards>`, the pieces in `<problem><lot>`
+//! and their outlines in `<polygons>`:
+//!
+//! ```xml
+//! <nesting>
+//!   <name>EXAMPLE</name>
+//!   <problem>
+//!     <boards>
+//!       <piece id="board0" quantity="1">
+//!         <component idPolygon="polygon0" type="0" xOffset="0" yOffset="0"/>
+//!       </piece>
+//!     </boards>
+//!     <lot>
+//!       <piece id="piece0" quantity="2">
+//!         <orientation>
+//!           <enumeration angle="0"/>
+//!           <enumeration angle="180"/>
+//!         </orientation>
+//!         <component idPolygon="polygon1" type="0" xOffset="0" yOffset="0"/>
+//!       </piece>
+//!     </lot>
+//!   </problem>
+//!   <polygons>
+//!     <polygon id="polygon0" nVertices="4">
+//!       <lines>
+//!         <segment n="1" x0="0" y0="0" x1="10" y1="0"/>
+//!         ...
+//!       </lines>
+//!     </polygon>
+//!   </polygons>
+//! </nesting>
+//! ```
+//!
+//! The board is used as given, for the strip packing instances build a
+//! [`StripPackingProblem`](crate::nesting::problem::StripPackingProblem) of its width instead.
+//! Angles are converted from degrees to radians.
+//! The components of a piece are joined into a single outline, they must overlap or touch
+//! and be of type 0, other types are rejected.
+//!
+//! Only the XML format is read, the older text files of some datasets are not supported.
+
+use crate::clip::Clippable;
+use crate::multi_polygon::MultiPolygon;
+use crate::nesting::problem::{IrregularBinPackingProblem, PieceDescription, RotationPolicy};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::{NumCast, Zero};
+use std::collections::HashMap;
+use std::io::Read;
+
+#[derive(Debug)]
+pub struct EsicupInstance<P: Polygon> {
+    pub name: String,
+    pub bin: MultiPolygon<P>,
+    pub pieces: Vec<PieceDescription<P>>,
+}
+
+impl<P: Polygon> From<EsicupInstance<P>> for IrregularBinPackingProblem<P> {
+    fn from(esicup: EsicupInstance<P>) -> Self {
+        IrregularBinPackingProblem::new(esicup.bin, esicup.pieces)
+    }
+}
+
+/// Parses an ESICUP XML instance into its board and pieces.
+pub fn parse_esicup<P, R>(mut reader: R) -> Result<EsicupInstance<P>>
+where
+    P: Clippable,
+    R: Read,
+{
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let document = roxmltree::Document::parse(&text)?;
+    let root = document.root_element();
+
+    let name = child(root, "name")
+        .and_then(|name| name.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let mut polygons = HashMap::new();
+    for polygon in children(
+        child(root, "polygons").ok_or(anyhow!("Missing polygons"))?,
+        "polygon",
+    ) {
+        let id = attribute(polygon, "id")?;
+        polygons.insert(id, parse_polygon(polygon)?);
+    }
+
+    let problem = child(root, "problem").ok_or(anyhow!("Missing problem"))?;
+    let board = child(problem, "boards")
+        .and_then(|boards| children(boards, "piece").next())
+        .ok_or(anyhow!("Missing board"))?;
+    let bin = parse_piece::<P>(board, &polygons)?;
+
+    let lot = child(problem, "lot").ok_or(anyhow!("Missing lot"))?;
+    let pieces = children(lot, "piece")
+        .map(|piece| {
+            let instances = attribute(piece, "quantity")?.parse::<usize>()?;
+            let mut allowed_rotations = vec![];
+            if let Some(orientation) = child(piece, "orientation") {
+                for angle in orientation.children().filter(|node| node.is_element()) {
+                    if angle.tag_name().name() != "enumeration" {
+                        return Err(anyhow!(
+                            "Unsupported orientation {} of piece {}",
+                            angle.tag_name().name(),
+                            attribute(piece, "id")?
+                        ));
+                    }
+                    let degrees = attribute(angle, "angle")?.parse::<f64>()?;
+                    allowed_rotations.push(value::<P::Point>(degrees.to_radians())?);
+                }
+            }
+            // pieces without an orientation keep the orientation they are drawn in
+            let rotations = if allowed_rotations.is_empty() {
+                RotationPolicy::Fixed(Zero::zero())
+            } else {
+                RotationPolicy::Discrete(allowed_rotations)
+            };
+            Ok(PieceDescription::new(
+                parse_piece(piece, &polygons)?,
+                rotations,
+                instances,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(EsicupInstance { name, bin, pieces })
+}
+
+/// Returns the union of the components of a piece.
+fn parse_piece<P: Clippable>(
+    piece: roxmltree::Node,
+    polygons: &HashMap<&str, Vec<(f64, f64)>>,
+) -> Result<MultiPolygon<P>> {
+    let id = attribute(piece, "id")?;
+    let mut union: Option<MultiPolygon<P>> = None;
+    for component in children(piece, "component") {
+        let kind = component.attribute("type").unwrap_or("0");
+        if kind != "0" {
+            return Err(anyhow!(
+                "Unsupported component type {} of piece {}",
+                kind,
+                id
+            ));
+        }
+        let polygon_id = attribute(component, "idPolygon")?;
+        let polygon = polygons
+            .get(polygon_id)
+            .ok_or(anyhow!("Unknown polygon {}", polygon_id))?;
+        let x_offset = offset(component, "xOffset")?;
+        let y_offset = offset(component, "yOffset")?;
+        let vertices = polygon
+            .iter()
+            .map(|(x, y)| {
+                Ok(P::Point::from_xy(
+                    value::<P::Point>(x + x_offset)?,
+                    value::<P::Point>(y + y_offset)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let component = MultiPolygon::new(P::from(vertices), vec![]);
+        union = Some(match union {
+            None => component,
+            Some(union) => {
+                let mut joined = union.union(&component);
+                if joined.len() != 1 {
+                    return Err(anyhow!("The components of piece {} are not connected", id));
+                }
+                joined.remove(0)
+            }
+        });
+    }
+    union.ok_or(anyhow!("Piece {} has no components", id))
+}
+
+/// Returns the vertices of a polygon, the start points of its segments ordered by `n`.
+fn parse_polygon(polygon: roxmltree::Node) -> Result<Vec<(f64, f64)>> {
+    let lines = child(polygon, "lines").ok_or(anyhow!(
+        "Polygon {} has no lines",
+        attribute(polygon, "id")?
+    ))?;
+    let mut segments = children(lines, "segment")
+        .map(|segment| {
+            Ok((
+                attribute(segment, "n")?.parse::<usize>()?,
+                (
+                    attribute(segment, "x0")?.parse::<f64>()?,
+                    attribute(segment, "y0")?.parse::<f64>()?,
+                ),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if segments.len() < 3 {
+        return Err(anyhow!(
+            "Polygon {} has less than 3 segments",
+            attribute(polygon, "id")?
+        ));
+    }
+    segments.sort_by_key(|(n, _)| *n);
+    Ok(segments.into_iter().map(|(_, start)| start).collect())
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn attribute<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name).ok_or(anyhow!(
+        "Missing attribute {} of {}",
+        name,
+        node.tag_name().name()
+    ))
+}
+
+fn offset(node: roxmltree::Node, name: &str) -> Result<f64> {
+    Ok(node
+        .attribute(name)
+        .map(str::parse)
+        .transpose()?
+        .unwrap_or(0.0))
+}
+
+fn value<T: Point2D>(value: f64) -> Result<T::Value> {
+    <T::Value as NumCast>::from(value).ok_or(anyhow!("{} is out of range", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::Polygon;
+    use crate::nesting::problem::RotationPolicy;
+    use std::f64::consts::PI;
+
+    const INSTANCE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<nesting xmlns="http://globalnest.fe.up.pt/nesting">
+  <name>EXAMPLE</name>
+  <problem>
+    <boards>
+      <piece id="board0" quantity="1">
+        <component idPolygon="polygon0" type="0" xOffset="0" yOffset="0"/>
+      </piece>
+    </boards>
+    <lot>
+      <piece id="piece0" quantity="3">
+        <orientation>
+          <enumeration angle="0"/>
+          <enumeration angle="180"/>
+        </orientation>
+        <component idPolygon="polygon1" type="0" xOffset="0" yOffset="0"/>
+      </piece>
+      <piece id="piece1" quantity="1">
+        <component idPolygon="polygon2" type="0" xOffset="0" yOffset="-2"/>
+        <component idPolygon="polygon1" type="0" xOffset="0" yOffset="0"/>
+      </piece>
+    </lot>
+  </problem>
+  <polygons>
+    <polygon id="polygon0" nVertices="4">
+      <lines>
+        <segment n="1" x0="0" y0="0" x1="100" y1="0"/>
+        <segment n="2" x0="100" y0="0" x1="100" y1="20"/>
+        <segment n="3" x0="100" y0="20" x1="0" y1="20"/>
+        <segment n="4" x0="0" y0="20" x1="0" y1="0"/>
+      </lines>
+    </polygon>
+    <polygon id="polygon1" nVertices="3">
+      <lines>
+        <segment n="2" x0="4" y0="0" x1="0" y1="3"/>
+        <segment n="1" x0="0" y0="0" x1="4" y1="0"/>
+        <segment n="3" x0="0" y0="3" x1="0" y1="0"/>
+      </lines>
+    </polygon>
+    <polygon id="polygon2" nVertices="4">
+      <lines>
+        <segment n="1" x0="0" y0="0" x1="4" y1="0"/>
+        <segment n="2" x0="4" y0="0" x1="4" y1="2"/>
+        <segment n="3" x0="4" y0="2" x1="0" y1="2"/>
+        <segment n="4" x0="0" y0="2" x1="0" y1="0"/>
+      </lines>
+    </polygon>
+  </polygons>
+</nesting>"#;
+
+    #[test]
+    fn test_parse_esicup() {
+        let instance = parse_esicup::<Polygon, _>(INSTANCE.as_bytes()).unwrap();
+        assert_eq!(instance.name, "EXAMPLE");
+        approx::assert_abs_diff_eq!(instance.bin.area(), 2000.0);
+        assert_eq!(instance.pieces.len(), 2);
+
+        let triangle = &instance.pieces[0];
+        assert_eq!(triangle.instances, 3);
+        approx::assert_abs_diff_eq!(triangle.piece.area(), 6.0);
+        assert_eq!(triangle.rotations, RotationPolicy::Discrete(vec![0.0, PI]));
+
+        // a rectangle with the triangle on top of it
+        let joined = &instance.pieces[1];
+        assert_eq!(joined.rotations, RotationPolicy::Fixed(0.0));
+        assert!(joined.piece.holes().is_empty());
+        approx::assert_abs_diff_eq!(joined.piece.area(), 14.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(joined.piece.bounding_box().min_y, -2.0);
+
+        let problem: IrregularBinPackingProblem<Polygon> = instance.into();
+        assert_eq!(problem.piece_descriptions().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_esicup_unknown_polygon() {
+        let instance = INSTANCE.replace("idPolygon=\"polygon2\"", "idPolygon=\"polygon9\"");
+        assert!(parse_esicup::<Polygon, _>(instance.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_esicup_rejects_unjoinable_components() {
+        let side_by_side = INSTANCE.replace(
+            "xOffset=\"0\" yOffset=\"-2\"",
+            "xOffset=\"5\" yOffset=\"0\"",
+        );
+        let error = parse_esicup::<Polygon, _>(side_by_side.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("not connected"), "{}", error);
+
+        let defect = INSTANCE.replace(
+            "type=\"0\" xOffset=\"0\" yOffset=\"-2\"",
+            "type=\"1\" xOffset=\"0\" yOffset=\"-2\"",
+        );
+        let error = parse_esicup::<Polygon, _>(defect.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("type 1"), "{}", error);
+    }
+}
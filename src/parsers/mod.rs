@@ -1 +1,4 @@
+pub mod dxf;
+pub mod esicup;
+pub mod svg;
 pub mod terashima;
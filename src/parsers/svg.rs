@@ -0,0 +1,941 @@
+//! Parser for nesting parts drawn in SVG
+//!
+//! Reads `<path>`, `<polygon>`, `<polyline>`, `<rect>`, `<circle>` and `<ellipse>` elements
+//! and applies their `transform` attributes and those of their ancestors.
+//! Every subpath is a closed contour, open ones are closed like SVG fills them.
+//!
+//! The element with a `data-bin` attribute is the bin, if no element has one the
+//! element with the largest area is. The contours of all other elements are pieces,
+//! a contour inside of another contour is a hole of it.
+//!
+//! Circular arcs stay arcs unless a transform skews or stretches them,
+//! elliptical arcs are approximated by cubic Béziers.
+
+use crate::curve::{
+    nest_curved_contours, CircularArc, CubicBezier, CurveSegment, CurvedContour,
+    CurvedMultiPolygon, Line, QuadraticBezier,
+};
+use crate::multi_polygon::MultiPolygon;
+use crate::nesting::problem::{IrregularBinPackingProblem, PieceDescription, RotationPolicy};
+use crate::point::Point2D;
+use crate::polygon::Polygon;
+use anyhow::{anyhow, Result};
+use num_traits::{FloatConst, NumCast, ToPrimitive, Zero};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+use std::io::Read;
+
+#[derive(Debug)]
+pub struct SvgInstance<P: Polygon> {
+    pub bin: MultiPolygon<P>,
+    pub pieces: Vec<MultiPolygon<P>>,
+    /// the bin with its original curves
+    pub curved_bin: CurvedMultiPolygon<P::Point>,
+    /// the pieces with their original curves, in the same order as `pieces`
+    pub curved_pieces: Vec<CurvedMultiPolygon<P::Point>>,
+}
+
+impl<P: Polygon> From<SvgInstance<P>> for IrregularBinPackingProblem<P> {
+    fn from(svg: SvgInstance<P>) -> Self {
+        IrregularBinPackingProblem::new(
+            svg.bin,
+            svg.pieces
+                .into_iter()
+                .map(|piece| PieceDescription::new(piece, RotationPolicy::Fixed(Zero::zero()), 1))
+                .collect(),
+        )
+    }
+}
+
+/// Parses the shapes of an SVG document into a bin and pieces.
+/// Curves are flattened so they deviate at most `tolerance` from the drawing.
+pub fn parse_svg<P, R>(
+    mut reader: R,
+    tolerance: <P::Point as Point2D>::Value,
+) -> Result<SvgInstance<P>>
+where
+    P: Polygon + From<Vec<P::Point>>,
+    <P::Point as Point2D>::Value: FloatConst,
+    R: Read,
+{
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let document = roxmltree::Document::parse(&text)?;
+    let tolerance_f64 = tolerance
+        .to_f64()
+        .ok_or(anyhow!("Tolerance is not a number"))?;
+
+    let mut elements = vec![];
+    collect_elements(document.root(), Transform::IDENTITY, &mut elements)?;
+    if elements.is_empty() {
+        return Err(anyhow!("No shapes found"));
+    }
+
+    let bin_index = match elements.iter().position(|element| element.bin) {
+        Some(index) => index,
+        None => {
+            let areas: Vec<f64> = elements
+                .iter()
+                .map(|element| element.area::<P::Point>(tolerance_f64))
+                .collect();
+            (0..elements.len())
+                .max_by(|a, b| areas[*a].total_cmp(&areas[*b]))
+                .unwrap()
+        }
+    };
+    let bin_element = elements.remove(bin_index);
+
+    let contours = |subpaths: Vec<Vec<Segment>>, transform: Transform| {
+        subpaths
+            .iter()
+            .map(|subpath| to_contour::<P::Point>(subpath, transform, tolerance_f64))
+            .collect::<Vec<_>>()
+    };
+    let curved_bin = nest_curved_contours(
+        contours(bin_element.subpaths, bin_element.transform),
+        tolerance,
+    )
+    .into_iter()
+    .map(|bin| {
+        let area = bin.to_multi_polygon::<P>(tolerance).area();
+        (area.to_f64().unwrap(), bin)
+    })
+    .max_by(|a, b| a.0.total_cmp(&b.0))
+    .map(|(_, bin)| bin)
+    .ok_or(anyhow!("The bin has no area"))?;
+
+    let curved_pieces = nest_curved_contours(
+        elements
+            .into_iter()
+            .flat_map(|element| contours(element.subpaths, element.transform))
+            .collect(),
+        tolerance,
+    );
+
+    Ok(SvgInstance {
+        bin: curved_bin.to_multi_polygon(tolerance),
+        pieces: curved_pieces
+            .iter()
+            .map(|piece| piece.to_multi_polygon(tolerance))
+            .collect(),
+        curved_bin,
+        curved_pieces,
+    })
+}
+
+/// An affine transformation mapping (x, y) to (a x + c y + e, b x + d y + f).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Transform {
+    const IDENTITY: Self = Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Returns the transformation applying `other` first and then `self`.
+    fn then(&self, other: &Self) -> Self {
+        Self::new(
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        )
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Returns the scale and rotation if the transformation keeps circles circular,
+    /// and whether it mirrors.
+    fn similarity(&self) -> Option<(f64, f64, bool)> {
+        let x_scale = self.a.hypot(self.b);
+        let y_scale = self.c.hypot(self.d);
+        let scale = x_scale.max(y_scale);
+        if (x_scale - y_scale).abs() > 1e-9 * scale
+            || (self.a * self.c + self.b * self.d).abs() > 1e-9 * scale * scale
+        {
+            return None;
+        }
+        let mirrored = self.a * self.d - self.b * self.c < 0.0;
+        Some((x_scale, self.b.atan2(self.a), mirrored))
+    }
+
+    /// An upper bound of how much the transformation stretches distances.
+    fn max_scale(&self) -> f64 {
+        (self.a * self.a + self.b * self.b + self.c * self.c + self.d * self.d).sqrt()
+    }
+}
+
+/// A segment in the coordinates of its element, before applying any transform.
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Line((f64, f64), (f64, f64)),
+    Quadratic((f64, f64), (f64, f64), (f64, f64)),
+    Cubic((f64, f64), (f64, f64), (f64, f64), (f64, f64)),
+    /// An elliptical arc from the angle `start` sweeping `sweep` radians
+    /// around `center` with radii `rx` and `ry`, the x axis rotated by `phi`.
+    Arc {
+        center: (f64, f64),
+        rx: f64,
+        ry: f64,
+        phi: f64,
+        start: f64,
+        sweep: f64,
+    },
+}
+
+/// The subpaths of an element and the transform to apply to them.
+struct Element {
+    subpaths: Vec<Vec<Segment>>,
+    transform: Transform,
+    bin: bool,
+}
+
+impl Element {
+    /// Returns the area of the largest subpath.
+    fn area<T>(&self, tolerance: f64) -> f64
+    where
+        T: Point2D,
+        T::Value: FloatConst,
+    {
+        self.subpaths
+            .iter()
+            .map(|subpath| {
+                let points = to_contour::<T>(subpath, self.transform, tolerance)
+                    .flatten(<T::Value as NumCast>::from(tolerance).unwrap());
+                let mut area = 0.0;
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    area += ((a.x() + b.x()) * (a.y() - b.y())).to_f64().unwrap();
+                }
+                (area / 2.0).abs()
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Walks the document collecting the shapes, skipping definitions that are not drawn directly.
+fn collect_elements(
+    node: roxmltree::Node,
+    parent: Transform,
+    elements: &mut Vec<Element>,
+) -> Result<()> {
+    for child in node.children().filter(|child| child.is_element()) {
+        let name = child.tag_name().name();
+        if matches!(
+            name,
+            "defs" | "symbol" | "clipPath" | "mask" | "pattern" | "marker"
+        ) {
+            continue;
+        }
+        let transform = match child.attribute("transform") {
+            Some(transform) => parent.then(&parse_transform(transform)?),
+            None => parent,
+        };
+        let subpaths = match name {
+            "path" => parse_path(child.attribute("d").unwrap_or(""))?,
+            "polygon" | "polyline" => {
+                let values = numbers(child.attribute("points").unwrap_or(""))?;
+                let points: Vec<(f64, f64)> =
+                    values.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+                vec![polyline(&points)]
+            }
+            "rect" => rect(child)?,
+            "circle" => {
+                let r = length(child, "r")?;
+                vec![ellipse(length(child, "cx")?, length(child, "cy")?, r, r)]
+            }
+            "ellipse" => vec![ellipse(
+                length(child, "cx")?,
+                length(child, "cy")?,
+                length(child, "rx")?,
+                length(child, "ry")?,
+            )],
+            _ => {
+                collect_elements(child, transform, elements)?;
+                continue;
+            }
+        };
+        let subpaths: Vec<Vec<Segment>> = subpaths.into_iter().filter(|s| !s.is_empty()).collect();
+        if !subpaths.is_empty() {
+            elements.push(Element {
+                subpaths,
+                transform,
+                bin: child.has_attribute("data-bin"),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the value of a length attribute ignoring its unit, zero if it is missing.
+fn length(node: roxmltree::Node, name: &str) -> Result<f64> {
+    let Some(value) = node.attribute(name) else {
+        return Ok(0.0);
+    };
+    let number: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+        .collect();
+    number
+        .parse()
+        .map_err(|_| anyhow!("Invalid length {:?} for attribute {}", value, name))
+}
+
+fn polyline(points: &[(f64, f64)]) -> Vec<Segment> {
+    (0..points.len())
+        .filter(|i| points[*i] != points[(i + 1) % points.len()])
+        .map(|i| Segment::Line(points[i], points[(i + 1) % points.len()]))
+        .collect()
+}
+
+fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<Segment> {
+    if rx <= 0.0 || ry <= 0.0 {
+        return vec![];
+    }
+    vec![Segment::Arc {
+        center: (cx, cy),
+        rx,
+        ry,
+        phi: 0.0,
+        start: 0.0,
+        sweep: TAU,
+    }]
+}
+
+fn rect(node: roxmltree::Node) -> Result<Vec<Vec<Segment>>> {
+    let (x, y) = (length(node, "x")?, length(node, "y")?);
+    let (width, height) = (length(node, "width")?, length(node, "height")?);
+    if width <= 0.0 || height <= 0.0 {
+        return Ok(vec![]);
+    }
+    // a missing radius is the same as the other one
+    let (mut rx, mut ry) = (length(node, "rx")?, length(node, "ry")?);
+    if !node.has_attribute("rx") {
+        rx = ry;
+    }
+    if !node.has_attribute("ry") {
+        ry = rx;
+    }
+    let (rx, ry) = (rx.clamp(0.0, width / 2.0), ry.clamp(0.0, height / 2.0));
+    if rx == 0.0 || ry == 0.0 {
+        return Ok(vec![polyline(&[
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ])]);
+    }
+    let corner = |cx: f64, cy: f64, start: f64| Segment::Arc {
+        center: (cx, cy),
+        rx,
+        ry,
+        phi: 0.0,
+        start,
+        sweep: FRAC_PI_2,
+    };
+    let (left, right, top, bottom) = (x + rx, x + width - rx, y + ry, y + height - ry);
+    let segments = vec![
+        Segment::Line((left, y), (right, y)),
+        corner(right, top, -FRAC_PI_2),
+        Segment::Line((x + width, top), (x + width, bottom)),
+        corner(right, bottom, 0.0),
+        Segment::Line((right, y + height), (left, y + height)),
+        corner(left, bottom, FRAC_PI_2),
+        Segment::Line((x, bottom), (x, top)),
+        corner(left, top, PI),
+    ];
+    // zero length lines where the corners meet
+    Ok(vec![segments
+        .into_iter()
+        .filter(|segment| !matches!(segment, Segment::Line(a, b) if a == b))
+        .collect()])
+}
+
+/// Parses the numbers of a list separated by whitespace and/or commas.
+fn numbers(text: &str) -> Result<Vec<f64>> {
+    let mut tokens = Tokens::new(text);
+    let mut values = vec![];
+    while tokens.skip_separators() {
+        values.push(tokens.number()?);
+    }
+    Ok(values)
+}
+
+/// Parses a transform attribute, a list of transform functions applied right to left.
+fn parse_transform(text: &str) -> Result<Transform> {
+    let mut transform = Transform::IDENTITY;
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let open = rest
+            .find('(')
+            .ok_or(anyhow!("Invalid transform {:?}", text))?;
+        let close = open
+            + rest[open..]
+                .find(')')
+                .ok_or(anyhow!("Invalid transform {:?}", text))?;
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let args = numbers(&rest[open + 1..close])?;
+        let arg = |i: usize| args.get(i).copied();
+        let next = match (name, args.len()) {
+            ("matrix", 6) => Transform::new(args[0], args[1], args[2], args[3], args[4], args[5]),
+            ("translate", 1 | 2) => {
+                Transform::new(1.0, 0.0, 0.0, 1.0, args[0], arg(1).unwrap_or(0.0))
+            }
+            ("scale", 1 | 2) => {
+                Transform::new(args[0], 0.0, 0.0, arg(1).unwrap_or(args[0]), 0.0, 0.0)
+            }
+            ("rotate", 1 | 3) => {
+                let (sin, cos) = args[0].to_radians().sin_cos();
+                let (cx, cy) = (arg(1).unwrap_or(0.0), arg(2).unwrap_or(0.0));
+                Transform::new(1.0, 0.0, 0.0, 1.0, cx, cy)
+                    .then(&Transform::new(cos, sin, -sin, cos, 0.0, 0.0))
+                    .then(&Transform::new(1.0, 0.0, 0.0, 1.0, -cx, -cy))
+            }
+            ("skewX", 1) => Transform::new(1.0, 0.0, args[0].to_radians().tan(), 1.0, 0.0, 0.0),
+            ("skewY", 1) => Transform::new(1.0, args[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+            _ => return Err(anyhow!("Invalid transform function {:?}", &rest[..=close])),
+        };
+        transform = transform.then(&next);
+        rest = rest[close + 1..].trim();
+    }
+    Ok(transform)
+}
+
+/// Reads numbers, flags and command letters of path data.
+struct Tokens<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text: text.as_bytes(),
+            position: 0,
+        }
+    }
+
+    /// Skips whitespace and commas, returns false at the end of the text.
+    fn skip_separators(&mut self) -> bool {
+        while self.position < self.text.len()
+            && (self.text[self.position].is_ascii_whitespace() || self.text[self.position] == b',')
+        {
+            self.position += 1;
+        }
+        self.position < self.text.len()
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_separators().then(|| self.text[self.position])
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        self.skip_separators();
+        let start = self.position;
+        let mut end = start;
+        if end < self.text.len() && matches!(self.text[end], b'+' | b'-') {
+            end += 1;
+        }
+        let mut dot = false;
+        while end < self.text.len()
+            && (self.text[end].is_ascii_digit() || (self.text[end] == b'.' && !dot))
+        {
+            dot |= self.text[end] == b'.';
+            end += 1;
+        }
+        // an exponent, but not the start of an arc command
+        if end < self.text.len()
+            && matches!(self.text[end], b'e' | b'E')
+            && end + 1 < self.text.len()
+            && (self.text[end + 1].is_ascii_digit() || matches!(self.text[end + 1], b'+' | b'-'))
+        {
+            end += 2;
+            while end < self.text.len() && self.text[end].is_ascii_digit() {
+                end += 1;
+            }
+        }
+        let token = std::str::from_utf8(&self.text[start..end]).unwrap();
+        let value = token
+            .parse()
+            .map_err(|_| anyhow!("Expected a number at position {}", start))?;
+        self.position = end;
+        Ok(value)
+    }
+
+    /// Reads an arc flag, which may be written without a separator before the next number.
+    fn flag(&mut self) -> Result<bool> {
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(anyhow!(
+                "Expected an arc flag at position {}",
+                self.position
+            )),
+        }
+    }
+
+    fn point(&mut self) -> Result<(f64, f64)> {
+        Ok((self.number()?, self.number()?))
+    }
+
+    /// True if the next token is a number continuing the previous command.
+    fn has_number(&mut self) -> bool {
+        matches!(self.peek(), Some(c) if c.is_ascii_digit() || matches!(c, b'.' | b'-' | b'+'))
+    }
+}
+
+/// Parses path data into closed subpaths.
+fn parse_path(data: &str) -> Result<Vec<Vec<Segment>>> {
+    let mut tokens = Tokens::new(data);
+    let mut subpaths = vec![];
+    let mut segments: Vec<Segment> = vec![];
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    // the last control point, for smooth curves
+    let mut last_control: Option<(u8, (f64, f64))> = None;
+
+    let close = |segments: &mut Vec<Segment>,
+                 subpaths: &mut Vec<Vec<Segment>>,
+                 current: (f64, f64),
+                 start: (f64, f64)| {
+        if segments.is_empty() {
+            return;
+        }
+        if current != start {
+            segments.push(Segment::Line(current, start));
+        }
+        subpaths.push(std::mem::take(segments));
+    };
+
+    let mut command = None;
+    while let Some(next) = tokens.peek() {
+        if next.is_ascii_alphabetic() {
+            tokens.position += 1;
+            command = Some(next);
+        } else if command.is_none() {
+            return Err(anyhow!("Path data does not start with a command"));
+        }
+        let c = command.unwrap();
+        let relative = c.is_ascii_lowercase();
+        let offset = |p: (f64, f64), current: (f64, f64)| {
+            if relative {
+                (p.0 + current.0, p.1 + current.1)
+            } else {
+                p
+            }
+        };
+        let mut control = None;
+        match c.to_ascii_uppercase() {
+            b'M' => {
+                close(&mut segments, &mut subpaths, current, start);
+                current = offset(tokens.point()?, current);
+                start = current;
+                // coordinates after a move are lines
+                command = Some(if relative { b'l' } else { b'L' });
+            }
+            b'L' => {
+                let end = offset(tokens.point()?, current);
+                segments.push(Segment::Line(current, end));
+                current = end;
+            }
+            b'H' => {
+                let x = tokens.number()?;
+                let end = (if relative { current.0 + x } else { x }, current.1);
+                segments.push(Segment::Line(current, end));
+                current = end;
+            }
+            b'V' => {
+                let y = tokens.number()?;
+                let end = (current.0, if relative { current.1 + y } else { y });
+                segments.push(Segment::Line(current, end));
+                current = end;
+            }
+            b'C' | b'S' => {
+                let control1 = if c.eq_ignore_ascii_case(&b'C') {
+                    offset(tokens.point()?, current)
+                } else {
+                    reflect(last_control, b'C', current)
+                };
+                let control2 = offset(tokens.point()?, current);
+                let end = offset(tokens.point()?, current);
+                segments.push(Segment::Cubic(current, control1, control2, end));
+                control = Some((b'C', control2));
+                current = end;
+            }
+            b'Q' | b'T' => {
+                let control1 = if c.eq_ignore_ascii_case(&b'Q') {
+                    offset(tokens.point()?, current)
+                } else {
+                    reflect(last_control, b'Q', current)
+                };
+                let end = offset(tokens.point()?, current);
+                segments.push(Segment::Quadratic(current, control1, end));
+                control = Some((b'Q', control1));
+                current = end;
+            }
+            b'A' => {
+                let (rx, ry) = tokens.point()?;
+                let rotation = tokens.number()?;
+                let large_arc = tokens.flag()?;
+                let sweep = tokens.flag()?;
+                let end = offset(tokens.point()?, current);
+                if let Some(arc) = endpoint_arc(
+                    current,
+                    end,
+                    rx,
+                    ry,
+                    rotation.to_radians(),
+                    large_arc,
+                    sweep,
+                ) {
+                    segments.push(arc);
+                }
+                current = end;
+            }
+            b'Z' => {
+                close(&mut segments, &mut subpaths, current, start);
+                current = start;
+                command = None;
+            }
+            _ => return Err(anyhow!("Unknown path command {:?}", c as char)),
+        }
+        last_control = control;
+        if command.is_some() && !tokens.has_number() {
+            command = None;
+        }
+    }
+    close(&mut segments, &mut subpaths, current, start);
+    Ok(subpaths
+        .into_iter()
+        .map(|subpath| {
+            subpath
+                .into_iter()
+                .filter(|segment| !matches!(segment, Segment::Line(a, b) if a == b))
+                .collect()
+        })
+        .collect())
+}
+
+/// Returns the control point of a smooth curve, the reflection of the previous
+/// curve's last control point or the current point if the previous segment is not alike.
+fn reflect(last: Option<(u8, (f64, f64))>, kind: u8, current: (f64, f64)) -> (f64, f64) {
+    match last {
+        Some((last_kind, control)) if last_kind == kind => {
+            (2.0 * current.0 - control.0, 2.0 * current.1 - control.1)
+        }
+        _ => current,
+    }
+}
+
+/// Converts an arc given by its end points to its center parameterization,
+/// following the implementation notes of the SVG specification.
+/// Returns a line if a radius is zero and None if the end points coincide.
+fn endpoint_arc(
+    from: (f64, f64),
+    to: (f64, f64),
+    rx: f64,
+    ry: f64,
+    phi: f64,
+    large_arc: bool,
+    sweep: bool,
+) -> Option<Segment> {
+    if from == to {
+        return None;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        return Some(Segment::Line(from, to));
+    }
+    let (sin, cos) = phi.sin_cos();
+    let (dx, dy) = ((from.0 - to.0) / 2.0, (from.1 - to.1) / 2.0);
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    // scale up radii too small to reach the end point
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc != sweep { 1.0 } else { -1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let center = (
+        cos * cx1 - sin * cy1 + (from.0 + to.0) / 2.0,
+        sin * cx1 + cos * cy1 + (from.1 + to.1) / 2.0,
+    );
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let start = angle(1.0, 0.0, (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle(
+        (x1 - cx1) / rx,
+        (y1 - cy1) / ry,
+        (-x1 - cx1) / rx,
+        (-y1 - cy1) / ry,
+    );
+    if !sweep && delta > 0.0 {
+        delta -= TAU;
+    } else if sweep && delta < 0.0 {
+        delta += TAU;
+    }
+    Some(Segment::Arc {
+        center,
+        rx,
+        ry,
+        phi,
+        start,
+        sweep: delta,
+    })
+}
+
+/// Returns the closed contour of the transformed segments.
+fn to_contour<T>(segments: &[Segment], transform: Transform, tolerance: f64) -> CurvedContour<T>
+where
+    T: Point2D,
+    T::Value: FloatConst,
+{
+    let point = |p: (f64, f64)| {
+        let (x, y) = transform.apply(p);
+        T::from_xy(
+            <T::Value as NumCast>::from(x).unwrap(),
+            <T::Value as NumCast>::from(y).unwrap(),
+        )
+    };
+    let value = |v: f64| <T::Value as NumCast>::from(v).unwrap();
+    let mut curves: Vec<CurveSegment<T>> = vec![];
+    for segment in segments.iter() {
+        match *segment {
+            Segment::Line(a, b) => curves.push(Line::new(point(a), point(b)).into()),
+            Segment::Quadratic(a, b, c) => {
+                curves.push(QuadraticBezier::new(point(a), point(b), point(c)).into())
+            }
+            Segment::Cubic(a, b, c, d) => {
+                curves.push(CubicBezier::new(point(a), point(b), point(c), point(d)).into())
+            }
+            Segment::Arc {
+                center,
+                rx,
+                ry,
+                phi,
+                start,
+                sweep,
+            } => match transform.similarity() {
+                Some((scale, rotation, mirrored)) if (rx - ry).abs() <= 1e-9 * rx => {
+                    let angle = start + phi;
+                    let (angle, sweep) = if mirrored {
+                        (rotation - angle, -sweep)
+                    } else {
+                        (rotation + angle, sweep)
+                    };
+                    curves.push(
+                        CircularArc::new(
+                            point(center),
+                            value(rx * scale),
+                            value(angle),
+                            value(sweep),
+                        )
+                        .into(),
+                    );
+                }
+                _ => {
+                    // a cubic approximating a quarter of the unit circle deviates about 2.7e-4,
+                    // decreasing with the sixth power of the angle
+                    let radius = rx.max(ry) * transform.max_scale();
+                    let mut pieces = (sweep.abs() / FRAC_PI_2).ceil().max(1.0);
+                    while radius * 2.7e-4 * (sweep.abs() / pieces / FRAC_PI_2).powi(6)
+                        > tolerance / 2.0
+                    {
+                        pieces *= 2.0;
+                    }
+                    let step = sweep / pieces;
+                    for i in 0..pieces as usize {
+                        let (a, b) = (start + step * i as f64, start + step * (i + 1) as f64);
+                        let k = 4.0 / 3.0 * (step / 4.0).tan();
+                        let on_ellipse = |x: f64, y: f64| {
+                            let (x, y) = (rx * x, ry * y);
+                            (
+                                center.0 + x * phi.cos() - y * phi.sin(),
+                                center.1 + x * phi.sin() + y * phi.cos(),
+                            )
+                        };
+                        curves.push(
+                            CubicBezier::new(
+                                point(on_ellipse(a.cos(), a.sin())),
+                                point(on_ellipse(a.cos() - k * a.sin(), a.sin() + k * a.cos())),
+                                point(on_ellipse(b.cos() + k * b.sin(), b.sin() - k * b.cos())),
+                                point(on_ellipse(b.cos(), b.sin())),
+                            )
+                            .into(),
+                        );
+                    }
+                }
+            },
+        }
+    }
+    CurvedContour::new(curves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernelf64::Polygon;
+
+    fn parse(svg: &str) -> SvgInstance<Polygon> {
+        parse_svg::<Polygon, _>(svg.as_bytes(), 0.001).unwrap()
+    }
+
+    #[test]
+    fn test_malformed_transforms() {
+        for transform in [
+            "a)(",
+            "translate(1",
+            "translate 1)",
+            "scale(1) )(",
+            "foo(1)",
+        ] {
+            let svg = format!(
+                r#"<svg><rect transform="{}" width="1" height="1"/></svg>"#,
+                transform
+            );
+            assert!(
+                parse_svg::<Polygon, _>(svg.as_bytes(), 0.001).is_err(),
+                "{}",
+                transform
+            );
+        }
+        let transform = parse_transform(" translate(1, 2),scale(2) ").unwrap();
+        assert_eq!(transform, parse_transform("matrix(2 0 0 2 1 2)").unwrap());
+    }
+
+    #[test]
+    fn test_parse_shapes() {
+        let instance = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <rect x="0" y="0" width="100" height="50"/>
+                <g transform="translate(10 10)">
+                    <rect x="0" y="0" width="10" height="20" rx="2"/>
+                    <circle cx="30" cy="10" r="5"/>
+                    <ellipse cx="50" cy="10" rx="6" ry="3" transform="rotate(30 50 10)"/>
+                    <polygon points="60,0 70,0 65,10"/>
+                    <polyline points="75 0, 85 0 85 10"/>
+                </g>
+                <defs><rect width="1000" height="1000"/></defs>
+            </svg>"#,
+        );
+        approx::assert_abs_diff_eq!(instance.bin.area(), 5000.0, epsilon = 1e-9);
+        assert_eq!(instance.pieces.len(), 5);
+        let areas: Vec<f64> = instance.pieces.iter().map(|p| p.area()).collect();
+        let expected = [200.0 - (4.0 - PI) * 4.0, 25.0 * PI, 18.0 * PI, 50.0, 50.0];
+        for expected in expected {
+            assert!(
+                areas.iter().any(|area| (area - expected).abs() < 0.05),
+                "{:?}",
+                areas
+            );
+        }
+        // the circle keeps its arc
+        assert!(instance
+            .curved_pieces
+            .iter()
+            .any(|p| matches!(p.outer.segments[0], CurveSegment::Arc(arc) if arc.radius == 5.0)));
+    }
+
+    #[test]
+    fn test_parse_path_commands_and_holes() {
+        let instance = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <path d="M0 0h50v50H0z" data-bin=""/>
+                <path d="M100 0 L200 0 L200 100 L100 100 Z m25 25 v50 h50 v-50 z"/>
+                <path d="M0,0 C0,10 10,10 10,0 S20,-10 20,0 Q20,10 10,10 T0,0z" transform="matrix(1 0 0 1 300 300)"/>
+                <path d="M10 300a5 5 0 1 0 10 0a5 5 0 1 0-10 0z"/>
+            </svg>"#,
+        );
+        // the flagged element is the bin even though it is not the largest
+        approx::assert_abs_diff_eq!(instance.bin.area(), 2500.0, epsilon = 1e-9);
+        assert_eq!(instance.pieces.len(), 3);
+        let frame = instance
+            .pieces
+            .iter()
+            .find(|p| !p.holes().is_empty())
+            .unwrap();
+        approx::assert_abs_diff_eq!(frame.area(), 10000.0 - 2500.0, epsilon = 1e-9);
+        let circle = instance
+            .pieces
+            .iter()
+            .find(|p| p.bounding_box().max_x < 100.0)
+            .unwrap();
+        approx::assert_abs_diff_eq!(circle.area(), 25.0 * PI, epsilon = 0.05);
+        let curve = instance
+            .pieces
+            .iter()
+            .find(|p| p.bounding_box().min_x >= 300.0 - 1e-9)
+            .unwrap();
+        approx::assert_abs_diff_eq!(curve.bounding_box().max_x, 320.0, epsilon = 1e-9);
+
+        let problem: IrregularBinPackingProblem<Polygon> = instance.into();
+        assert_eq!(problem.piece_descriptions().len(), 3);
+    }
+
+    fn end(arc: Segment) -> (f64, f64) {
+        let Segment::Arc {
+            center,
+            rx,
+            ry,
+            phi,
+            start,
+            sweep,
+        } = arc
+        else {
+            panic!("expected an arc");
+        };
+        let (x, y) = (rx * (start + sweep).cos(), ry * (start + sweep).sin());
+        (
+            center.0 + x * phi.cos() - y * phi.sin(),
+            center.1 + x * phi.sin() + y * phi.cos(),
+        )
+    }
+
+    #[test]
+    fn test_endpoint_arc() {
+        // a quarter of an ellipse rotated by 90 degrees
+        let arc = endpoint_arc((0.0, 0.0), (2.0, 1.0), 1.0, 2.0, FRAC_PI_2, false, true).unwrap();
+        let Segment::Arc { center, sweep, .. } = arc else {
+            panic!("expected an arc");
+        };
+        approx::assert_abs_diff_eq!(sweep.abs(), FRAC_PI_2, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(end(arc).0, 2.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(end(arc).1, 1.0, epsilon = 1e-9);
+        assert!(center == (0.0, 1.0) || center == (2.0, 0.0));
+        // radii too small are scaled up to a half ellipse
+        let arc = endpoint_arc((0.0, 0.0), (10.0, 0.0), 1.0, 1.0, 0.0, false, true).unwrap();
+        let Segment::Arc { rx, sweep, .. } = arc else {
+            panic!("expected an arc");
+        };
+        approx::assert_abs_diff_eq!(rx, 5.0, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(sweep.abs(), PI, epsilon = 1e-9);
+    }
+}
@@ -16,12 +16,13 @@
 //!   where each piece is placed in the optimal solution.
 
 use crate::multi_polygon::MultiPolygon;
-use crate::nesting::problem::PieceDescription;
+use crate::nesting::problem::{IrregularBinPackingSolution, PieceDescription, RotationPolicy};
 use crate::{nesting::problem::IrregularBinPackingProblem, point::Point2D};
 use crate::polygon::Polygon;
 use anyhow::{anyhow, Result};
-use num_traits::Zero;
+use num_traits::{Float, Zero};
 use std::{
+    fmt::{self, Display, Formatter},
     io::{BufRead, BufReader, Read},
     str::FromStr,
 };
@@ -39,7 +40,7 @@ impl<P: Polygon> From<TerashimaInstance<P>> for IrregularBinPackingProblem<P> {
             terashima.pieces.into_iter().map(|p| {
                 PieceDescription::new(
                 MultiPolygon::new(p, vec![]),
-                vec![],
+                RotationPolicy::Fixed(Zero::zero()),
                 1
                 )
             }).collect()
@@ -115,10 +116,162 @@ where
     Ok(TerashimaInstance { bin, pieces })
 }
 
+/// The optimum layout of an instance, read from its `Op<INSTANCE>.txt` file.
+#[derive(Debug)]
+pub struct TerashimaOptimum<P: Polygon> {
+    pub bin: P,
+    /// the pieces placed in each bin, in bin coordinates
+    pub bins: Vec<Vec<P>>,
+}
+
+impl<P: Polygon> TerashimaOptimum<P> {
+    pub fn bins_used(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// Returns the fraction of each bin's area covered by pieces
+    pub fn bin_utilization(&self) -> Vec<<P::Point as Point2D>::Value> {
+        let bin_area = self.bin.area().abs();
+        self.bins
+            .iter()
+            .map(|pieces| {
+                pieces
+                    .iter()
+                    .map(|piece| piece.area().abs())
+                    .fold(Zero::zero(), |sum: <P::Point as Point2D>::Value, area| {
+                        sum + area
+                    })
+                    / bin_area
+            })
+            .collect()
+    }
+}
+
+/// Parses an `Op<INSTANCE>.txt` file into the pieces of each bin.
+pub fn parse_terashima_optimum<P, R>(reader: R) -> Result<TerashimaOptimum<P>>
+where
+    P: Polygon + From<Vec<P::Point>>,
+    R: Read,
+    P::Point: From<(
+        <<P as Polygon>::Point as Point2D>::Value,
+        <<P as Polygon>::Point as Point2D>::Value,
+    )>,
+    <<P as Polygon>::Point as Point2D>::Value: FromStr,
+    <<<P as Polygon>::Point as Point2D>::Value as FromStr>::Err:
+        std::error::Error + Send + Sync + 'static,
+{
+    let reader = BufReader::new(reader);
+    let mut lines = reader.lines();
+
+    // Parse number of bins followed by the number of pieces in each
+    let counts: Vec<usize> = lines
+        .next()
+        .ok_or(anyhow!("Missing number of bins"))??
+        .split_whitespace()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<_>, _>>()?;
+    let (n_bins, pieces_per_bin) = counts
+        .split_first()
+        .ok_or(anyhow!("Missing number of bins"))?;
+    if *n_bins != pieces_per_bin.len() {
+        return Err(anyhow!(
+            "Expected the number of pieces of {} bins, got {}",
+            n_bins,
+            pieces_per_bin.len()
+        ));
+    }
+
+    let TerashimaInstance { bin, pieces } = parse_terashima::<P, _>(
+        format!(
+            "{}\n{}",
+            pieces_per_bin.iter().sum::<usize>(),
+            lines.collect::<Result<Vec<_>, _>>()?.join("\n")
+        )
+        .as_bytes(),
+    )?;
+
+    let mut pieces = pieces.into_iter();
+    let bins = pieces_per_bin
+        .iter()
+        .map(|n| pieces.by_ref().take(*n).collect())
+        .collect();
+
+    Ok(TerashimaOptimum { bin, bins })
+}
+
+/// Bins used and utilization of a solution next to those of the optimum
+#[derive(Debug, Clone)]
+pub struct TerashimaComparison<T> {
+    pub optimum_bins: usize,
+    pub solution_bins: usize,
+    pub optimum_utilization: Vec<T>,
+    pub solution_utilization: Vec<T>,
+}
+
+/// Compares a solution for an instance with the optimum layout of the instance.
+pub fn compare_to_optimum<P: Polygon>(
+    optimum: &TerashimaOptimum<P>,
+    problem: &IrregularBinPackingProblem<P>,
+    solution: &IrregularBinPackingSolution<P>,
+) -> TerashimaComparison<<P::Point as Point2D>::Value> {
+    TerashimaComparison {
+        optimum_bins: optimum.bins_used(),
+        solution_bins: solution.bins_used(),
+        optimum_utilization: optimum.bin_utilization(),
+        solution_utilization: solution.bin_utilization(problem),
+    }
+}
+
+impl<T> TerashimaComparison<T> {
+    /// Returns how many bins the solution uses more than the optimum
+    pub fn bin_gap(&self) -> isize {
+        self.solution_bins as isize - self.optimum_bins as isize
+    }
+
+    /// Returns the bin gap relative to the bins of the optimum,
+    /// None if the optimum uses no bins
+    pub fn relative_gap(&self) -> Option<f64> {
+        if self.optimum_bins == 0 {
+            return None;
+        }
+        Some(self.bin_gap() as f64 / self.optimum_bins as f64)
+    }
+}
+
+impl<T: Display> Display for TerashimaComparison<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "bins used: {} (optimum {})",
+            self.solution_bins, self.optimum_bins
+        )?;
+        match self.relative_gap() {
+            Some(gap) => writeln!(f, "gap: {} bins ({:.1}%)", self.bin_gap(), 100.0 * gap)?,
+            None => writeln!(f, "gap: {} bins", self.bin_gap())?,
+        }
+        for bin_id in 0..self.solution_bins.max(self.optimum_bins) {
+            let utilization = |u: &[T]| {
+                u.get(bin_id)
+                    .map(|u| format!("{:.3}", u))
+                    .unwrap_or("-".to_string())
+            };
+            writeln!(
+                f,
+                "bin {}: utilization {} (optimum {})",
+                bin_id,
+                utilization(&self.solution_utilization),
+                utilization(&self.optimum_utilization)
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::kernelf64::Polygon;
+    use crate::kernelf64::{Point2D, Polygon};
+    use crate::nesting::problem::IrregularBinPackingPlacement;
     use std::fs::File;
     use std::path::PathBuf;
 
@@ -131,4 +284,43 @@ mod tests {
         let instance = result.unwrap();
         assert_eq!(instance.pieces.len(), 15);
     }
+
+    #[test]
+    fn test_compare_to_optimum() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/Terashima2/OpTV001C5.txt");
+        let optimum = parse_terashima_optimum::<Polygon, _>(File::open(path).unwrap()).unwrap();
+        assert_eq!(optimum.bins_used(), 5);
+        assert!(optimum.bins.iter().all(|pieces| pieces.len() == 3));
+        for utilization in optimum.bin_utilization() {
+            approx::assert_abs_diff_eq!(utilization, 1.0, epsilon = 1e-6);
+        }
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/Terashima2/TV001C5.txt");
+        let problem = IrregularBinPackingProblem::from(
+            parse_terashima::<Polygon, _>(File::open(path).unwrap()).unwrap(),
+        );
+        // one piece per bin
+        let solution = IrregularBinPackingSolution::new(
+            (0..problem.piece_descriptions().len())
+                .map(|i| IrregularBinPackingPlacement::new(i, i, Point2D { x: 0.0, y: 0.0 }, 0.0))
+                .collect(),
+        );
+        let comparison = compare_to_optimum(&optimum, &problem, &solution);
+        assert_eq!(comparison.bin_gap(), 10);
+        approx::assert_abs_diff_eq!(comparison.relative_gap().unwrap(), 2.0);
+        assert_eq!(comparison.to_string().lines().count(), 2 + 15);
+
+        let no_optimum = TerashimaComparison::<f64> {
+            optimum_bins: 0,
+            solution_bins: 1,
+            optimum_utilization: vec![],
+            solution_utilization: vec![1.0],
+        };
+        assert_eq!(no_optimum.relative_gap(), None);
+        assert!(no_optimum
+            .to_string()
+            .starts_with("bins used: 1 (optimum 0)\ngap: 1 bins\n"));
+    }
 }
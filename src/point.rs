@@ -44,6 +44,11 @@ pub trait Point2D:
         )
     }
 
+    /// Returns the point reflected across the y axis
+    fn reflect(&self) -> Self {
+        Self::from_xy(-self.x(), self.y())
+    }
+
     fn translate(&self, other: &Self) -> Self {
         Self::from_xy(self.x() + other.x(), self.y() + other.y())
     }
@@ -334,6 +339,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test point inside the polygon
@@ -361,6 +367,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test point inside the triangle
@@ -380,6 +387,7 @@ mod tests {
             vertices: vec![Point2D { x: 0.0, y: 0.0 }, Point2D { x: 1.0, y: 1.0 }],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
         let p8 = Point2D { x: 0.5, y: 0.5 };
         assert_eq!(p8.in_polygon(&invalid_polygon), None);
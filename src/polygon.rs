@@ -29,21 +29,54 @@ pub trait Polygon: Clone + std::fmt::Debug {
     /// Sets the rotation of the polygon.
     fn set_rotation(&mut self, rotation: <<Self as Polygon>::Point as Point2D>::Value);
 
+    /// Returns true if polygons of this type can be mirrored with `set_mirrored`.
+    /// Problem builders reject pieces that may be mirrored when it is false.
+    fn can_mirror() -> bool {
+        false
+    }
+
+    /// Returns true if the polygon is reflected across its local y axis before it is rotated.
+    /// Polygons that cannot be mirrored never are.
+    fn mirrored(&self) -> bool {
+        false
+    }
+
+    /// Sets whether the polygon is reflected across its local y axis before it is rotated.
+    /// The transformed vertices of a mirrored polygon wind the other way around than its
+    /// local vertices. `area` and the no fit polygon functions only look at the local vertices,
+    /// so reflect the vertices themselves before computing no fit polygons of mirrored pieces.
+    /// Panics when mirroring a polygon that cannot be mirrored, see `can_mirror`.
+    fn set_mirrored(&mut self, mirrored: bool) {
+        assert!(!mirrored, "polygon cannot be mirrored");
+    }
+
     /// Returns the number of vertices of the polygon.
     fn length(&self) -> usize;
 
     /// Returns an inorder iterator over the vertices of the polygon.
     /// Coordinates are after any transformations to the polygon.
     fn iter_vertices(&self) -> impl Iterator<Item = Self::Point> {
-        self.iter_vertices_local()
-            .map(|vertex| vertex.rotate(self.rotation()) + self.offset())
+        self.iter_vertices_local().map(|vertex| {
+            let vertex = if self.mirrored() {
+                vertex.reflect()
+            } else {
+                *vertex
+            };
+            vertex.rotate(self.rotation()) + self.offset()
+        })
     }
 
     /// Returns an inorder iterator over the segments of the polygon.
     /// Coordinates are after any transformations to the polygon.
     fn iter_segments(&self) -> impl Iterator<Item = Self::Segment> + Clone {
-        self.iter_segments_local()
-            .map(|segment| segment.rotate(self.rotation()) + self.offset())
+        self.iter_segments_local().map(|segment| {
+            let segment = if self.mirrored() {
+                segment.reflect()
+            } else {
+                segment
+            };
+            segment.rotate(self.rotation()) + self.offset()
+        })
     }
 
     fn iter_poly_segments_3(
@@ -124,7 +157,8 @@ pub trait Polygon: Clone + std::fmt::Debug {
     }
 
     /// Return the area of the polygon assuming no self intersections.
-    /// A negative area indicates counter-clockwise winding.
+    /// A negative area indicates counter-clockwise winding of the local vertices,
+    /// mirroring is not taken into account.
     fn area(&self) -> <<Self as Polygon>::Point as Point2D>::Value {
         let two = <<Self as Polygon>::Point as Point2D>::Value::one()
             + <<Self as Polygon>::Point as Point2D>::Value::one();
@@ -297,6 +331,7 @@ pub trait Polygon: Clone + std::fmt::Debug {
     }
 }
 
+#[cfg(test)]
 mod tests {
     #[test]
     fn test_bounding_box() {
@@ -312,6 +347,7 @@ mod tests {
             ],
             offset: Point2D { x: 1.0, y: 1.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         let bbox = square.bounding_box();
@@ -328,6 +364,7 @@ mod tests {
             ],
             offset: Point2D { x: -1.0, y: -1.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         let bbox = triangle.bounding_box();
@@ -337,6 +374,107 @@ mod tests {
         assert_eq!(bbox.max_y, 1.0);
     }
     #[test]
+    fn test_mirrored_vertices() {
+        use super::Polygon as _;
+        use crate::kernelf64::{Point2D, Polygon};
+        use crate::no_fit_polygon::ComputeNoFitPolygon;
+
+        let triangle = Polygon {
+            vertices: vec![
+                Point2D { x: 0.0, y: 0.0 },
+                Point2D { x: 2.0, y: 0.0 },
+                Point2D { x: 0.0, y: 1.0 },
+            ],
+            offset: Point2D { x: 1.0, y: 1.0 },
+            rotation: std::f64::consts::FRAC_PI_2,
+            mirrored: true,
+        };
+
+        let expected = [(1.0, 1.0), (1.0, -1.0), (0.0, 1.0)];
+        for (i, (vertex, (x, y))) in triangle.iter_vertices().zip(expected).enumerate() {
+            approx::assert_abs_diff_eq!(vertex.x, x, epsilon = 1e-9);
+            approx::assert_abs_diff_eq!(vertex.y, y, epsilon = 1e-9);
+            approx::assert_abs_diff_eq!(triangle.get_vertex(i).x, x, epsilon = 1e-9);
+            approx::assert_abs_diff_eq!(triangle.get_vertex(i).y, y, epsilon = 1e-9);
+        }
+        let segment = triangle.iter_segments().next().unwrap();
+        approx::assert_abs_diff_eq!(segment.end.y, -1.0, epsilon = 1e-9);
+        // mirroring turns the transformed winding around, the area stays that of the local vertices
+        assert!(triangle.area() < 0.0);
+        let vertices: Vec<_> = triangle.iter_vertices().collect();
+        assert!(Polygon::from(vertices).area() > 0.0);
+    }
+
+    /// A polygon that implements none of the optional methods
+    #[derive(Debug, Clone)]
+    struct Fixed(crate::kernelf64::Polygon);
+
+    impl super::Polygon for Fixed {
+        type Point = crate::kernelf64::Point2D;
+        type Segment = crate::kernelf64::Segment;
+
+        fn iter_vertices_local(&self) -> impl Iterator<Item = &Self::Point> {
+            self.0.iter_vertices_local()
+        }
+
+        fn iter_segments_local(&self) -> impl Iterator<Item = Self::Segment> + Clone {
+            self.0.iter_segments_local()
+        }
+
+        fn offset(&self) -> Self::Point {
+            self.0.offset()
+        }
+
+        fn set_offset(&mut self, offset: Self::Point) {
+            self.0.set_offset(offset);
+        }
+
+        fn rotation(&self) -> f64 {
+            self.0.rotation()
+        }
+
+        fn set_rotation(&mut self, rotation: f64) {
+            self.0.set_rotation(rotation);
+        }
+
+        fn length(&self) -> usize {
+            self.0.length()
+        }
+    }
+
+    #[test]
+    fn test_mirroring_is_optional() {
+        use super::Polygon as _;
+
+        let mut square = Fixed(crate::kernelf64::Polygon::from_tuples([
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+        ]));
+        square.set_mirrored(false);
+        assert!(!square.mirrored());
+        assert_eq!(square.bounding_box().max_x, 1.0);
+        let piece = crate::multi_polygon::MultiPolygon::new(square.clone(), vec![]);
+        let result = std::panic::catch_unwind(move || square.set_mirrored(true));
+        assert!(result.is_err());
+
+        assert!(!Fixed::can_mirror());
+        let mut description =
+            crate::nesting::problem::PieceDescription::new(piece.clone(), vec![0.0], 1);
+        description.allow_mirror = true;
+        let error = crate::nesting::problem::IrregularBinPackingProblem::builder()
+            .bin(piece)
+            .piece_descriptions([description])
+            .build()
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("cannot be mirrored"),
+            "{}",
+            error
+        );
+    }
+    #[test]
     fn test_area() {
         use super::Polygon as _;
         use crate::kernelf64::{Point2D, Polygon};
@@ -351,6 +489,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test the area of the square
@@ -365,6 +504,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test the area of the triangle
@@ -380,6 +520,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test the area of the counter-clockwise polygon (should be negative)
@@ -401,6 +542,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         let segments: Vec<(Segment, Segment, Segment)> = square.iter_poly_segments_3().collect();
@@ -455,6 +597,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         let square2 = Polygon {
@@ -466,6 +609,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test intersecting polygons
@@ -482,6 +626,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         let square4 = Polygon {
@@ -493,6 +638,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
 
         // Test non-intersecting polygons
@@ -515,6 +661,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
         let mut polygon2 = polygon1.clone();
         polygon2.translate(-2.0, 0.0);
@@ -559,6 +706,7 @@ mod tests {
             ],
             offset: Point2D { x: 0.0, y: 0.0 },
             rotation: 0.0,
+            mirrored: false,
         };
         let mut polygon2 = polygon1.clone();
         polygon2.translate(3.0, 1.0);
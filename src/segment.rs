@@ -25,6 +25,11 @@ pub trait Segment:
         Self::from((self.start().rotate(angle), self.end().rotate(angle)))
     }
 
+    /// Returns the segment reflected across the y axis
+    fn reflect(&self) -> Self {
+        Self::from((self.start().reflect(), self.end().reflect()))
+    }
+
     /// Returns the intersection of this segment with a polygon.
     /// The intersections are ordered by distance from the start of this segment.
     fn intersects_polygon<P>(&self, other: &P) -> Vec<Self::Point>